@@ -8,7 +8,7 @@ async fn fetch_block(rest: &bitcoin_rest::Context, height: u32) {
 
 fn bench(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
-    let rest = bitcoin_rest::new(bitcoin_rest::DEFAULT_ENDPOINT);
+    let rest = bitcoin_rest::try_new(bitcoin_rest::DEFAULT_ENDPOINT).unwrap();
     c.bench_function("Fetch block at height 1", |b| b.iter(|| {
         rt.block_on(async {
             fetch_block(&rest, 1).await;
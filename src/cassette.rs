@@ -0,0 +1,121 @@
+//! Record and replay HTTP interactions (the "cassette" pattern).
+//!
+//! Available with the `test-record` feature. In `Record` mode every response is saved under the
+//! cassette directory, while in `Replay` mode responses are served from there without touching the network.
+//! This lets network-dependent tests run deterministically without a live node.
+//!
+//! A context created by [try_new](../fn.try_new.html) picks up the cassette from the environment
+//! (see [Cassette::from_env](./struct.Cassette.html#method.from_env)).
+
+use std::path::PathBuf;
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::hashes::hex::ToHex;
+
+/// The environment variable selecting the cassette mode (`record` or `replay`).
+pub const MODE_ENV_NAME: &str = "BITCOIN_REST_CASSETTE";
+
+/// The environment variable specifying the cassette directory.
+pub const DIR_ENV_NAME: &str = "BITCOIN_REST_CASSETTE_DIR";
+
+/// The cassette directory used if `BITCOIN_REST_CASSETTE_DIR` is not set.
+pub const DEFAULT_DIR: &str = "cassettes";
+
+/// File names longer than this are shortened (most file systems allow up to 255 bytes).
+const MAX_FILE_NAME_LEN: usize = 200;
+
+/// The length of the readable prefix kept in a shortened file name.
+const SHORTENED_PREFIX_LEN: usize = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Send requests and save the responses.
+    Record,
+    /// Serve the saved responses instead of sending requests.
+    Replay,
+}
+
+#[derive(Debug, Clone)]
+pub struct Cassette {
+    dir: PathBuf,
+    mode: Mode,
+}
+
+impl Cassette {
+    pub fn new<P: Into<PathBuf>>(dir: P, mode: Mode) -> Self {
+        Cassette {
+            dir: dir.into(),
+            mode,
+        }
+    }
+    /// Create a cassette from the `BITCOIN_REST_CASSETTE` and `BITCOIN_REST_CASSETTE_DIR` environment variables.
+    ///
+    /// Returns `None` unless `BITCOIN_REST_CASSETTE` is `record` or `replay`.
+    pub fn from_env() -> Option<Self> {
+        let mode = match std::env::var(MODE_ENV_NAME).ok()?.as_str() {
+            "record" => Mode::Record,
+            "replay" => Mode::Replay,
+            _ => return None,
+        };
+        let dir = std::env::var(DIR_ENV_NAME).unwrap_or(DEFAULT_DIR.to_string());
+        Some(Self::new(dir, mode))
+    }
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+    /// The file name of the recording for `url`.
+    ///
+    /// A long URL (e.g. getutxos with many outpoints) is shortened to a readable prefix
+    /// followed by the SHA256 of the whole URL.
+    fn file_path(&self, url: &str) -> PathBuf {
+        let mut name: String = url.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+            .collect();
+        if name.len() > MAX_FILE_NAME_LEN {
+            name.truncate(SHORTENED_PREFIX_LEN);
+            name.push('-');
+            name.push_str(&sha256::Hash::hash(url.as_bytes()).to_hex());
+        }
+        self.dir.join(name)
+    }
+    /// Save the response (status code followed by the body) for `url`.
+    pub(crate) fn save(&self, url: &str, status: u16, body: &[u8]) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let mut data = status.to_be_bytes().to_vec();
+        data.extend_from_slice(body);
+        std::fs::write(self.file_path(url), data)
+    }
+    /// Load the response (status code and body) saved for `url`.
+    pub(crate) fn load(&self, url: &str) -> std::io::Result<(u16, bytes::Bytes)> {
+        let data = std::fs::read(self.file_path(url))?;
+        if data.len() < 2 {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "truncated cassette"));
+        }
+        let status = u16::from_be_bytes([data[0], data[1]]);
+        Ok((status, bytes::Bytes::copy_from_slice(&data[2..])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn save_and_load() {
+        let dir = std::env::temp_dir().join(format!("bitcoin-rest-cassette-{}", std::process::id()));
+        let cassette = Cassette::new(&dir, Mode::Record);
+        let url = "http://localhost:8332/rest/chaininfo.json";
+        cassette.save(url, 200, b"{}").unwrap();
+        assert!(dir.join("http___localhost_8332_rest_chaininfo.json").exists());
+        assert_eq!(cassette.load(url).unwrap(), (200, bytes::Bytes::from_static(b"{}")));
+        assert!(cassette.load("http://localhost:8332/rest/other.json").is_err());
+        let long_url = |last: u32| {
+            let outpoints: Vec<String> = (0..=last).map(|vout| format!("{}-{}", "00".repeat(32), vout)).collect();
+            format!("http://localhost:8332/rest/getutxos/{}.json", outpoints.join("/"))
+        };
+        cassette.save(&long_url(14), 200, b"14").unwrap();
+        cassette.save(&long_url(13), 200, b"13").unwrap();
+        assert_eq!(cassette.load(&long_url(14)).unwrap(), (200, bytes::Bytes::from_static(b"14")));
+        assert_eq!(cassette.load(&long_url(13)).unwrap(), (200, bytes::Bytes::from_static(b"13")));
+        assert!(cassette.file_path(&long_url(14)).file_name().unwrap().len() <= 255);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -4,21 +4,72 @@
 //! converts them to [rust-bitcoin](https://github.com/rust-bitcoin/rust-bitcoin) objects.
 //! 
 //! For details, please see [Context](./struct.Context.html).
+//!
+//! # Cancellation
+//!
+//! No method spawns tasks: the requests of the batch helpers (e.g. [Context::blocks](./struct.Context.html#method.blocks)
+//! and [Context::export_blocks](./struct.Context.html#method.export_blocks)) are driven by the returned future itself.
+//! Dropping the future (e.g. by `tokio::select!` or `tokio::time::timeout`) drops the requests in flight,
+//! which closes their connections, and nothing keeps running in the background.
 
-#[cfg(feature="softforks")]
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::future::Future;
+use std::time::Duration;
+use futures::stream::{self, StreamExt, TryStreamExt};
 pub use bytes;
 pub use serde;
 pub use reqwest;
 pub use bitcoin;
+pub use url;
 use serde::{Deserialize, Serialize};
 use bitcoin::hash_types::{BlockHash, Txid};
 use bitcoin::blockdata::block::{Block, BlockHeader};
-use bitcoin::blockdata::transaction::Transaction;
-use bitcoin::consensus::Decodable;
+use bitcoin::blockdata::transaction::{OutPoint, Transaction, TxOut};
+use bitcoin::consensus::{Decodable, Encodable};
+use bitcoin::consensus::encode::VarInt;
+use bitcoin::hashes::Hash;
+use bitcoin::hashes::hex::{FromHex, ToHex};
+use bitcoin::Amount;
+use bitcoin::SignedAmount;
+use bitcoin::Network;
+use bitcoin::consensus::params::Params;
+
+#[cfg(feature="test-record")]
+pub mod cassette;
 
 pub const DEFAULT_ENDPOINT: &str = "http://localhost:8332/rest";
 
+/// The maximum number of outpoints Bitcoin Core accepts in a single
+/// [/getutxos](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#query-utxo-set) request.
+pub const MAX_GETUTXOS_OUTPOINTS: usize = 15;
+
+/// The maximum `count` Bitcoin Core accepts in a single
+/// [/headers](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#blockheaders) request.
+pub const MAX_HEADERS_RESULTS: u32 = 2000;
+
+/// The number of blocks between difficulty adjustments.
+pub const DIFFICULTY_ADJUSTMENT_INTERVAL: u32 = 2016;
+
+/// The expected time span (in seconds) of a difficulty adjustment window.
+pub const POW_TARGET_TIMESPAN: u32 = 14 * 24 * 60 * 60;
+
+/// The expected time (in seconds) between blocks.
+pub const POW_TARGET_SPACING: u32 = 10 * 60;
+
+/// The number of blocks between block subsidy halvings (`150` on regtest).
+pub const SUBSIDY_HALVING_INTERVAL: u32 = 210_000;
+
+/// The number of blocks between block subsidy halvings on `network`.
+///
+/// rust-bitcoin 0.26's `Params` has no such field, so it is derived from the network.
+pub fn subsidy_halving_interval(network: Network) -> u32 {
+    match network {
+        Network::Regtest => 150,
+        _ => SUBSIDY_HALVING_INTERVAL,
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Softfork {
     #[serde(rename="type")]
@@ -35,17 +86,277 @@ pub struct ChainInfo {
     pub headers: u32,
     pub bestblockhash: String,
     pub difficulty: f64,
+    /// The tip block time. Available on Bitcoin Core >= 23.0 (`0` otherwise).
+    #[serde(default)]
+    pub time: u32,
     pub mediantime: u32,
     pub verificationprogress: f64,
+    #[serde(default)]
+    pub initialblockdownload: bool,
     pub chainwork: String,
     pub pruned: bool,
     #[serde(default)]
     pub pruneheight: u32,
-    #[cfg(feature="softforks")]
-    pub softforks: HashMap<String, Softfork>,
+    /// `None` if the node does not report softforks in the `{"name": {...}}` form
+    /// (e.g. Bitcoin Core >= 23.0 or older forks using the legacy array form).
+    #[serde(default, deserialize_with="deserialize_softforks")]
+    pub softforks: Option<HashMap<String, Softfork>>,
+    /// Multiple warnings (Bitcoin Core >= 28.0) are joined with newlines.
+    #[serde(deserialize_with="deserialize_warnings")]
     pub warnings: String,
 }
 
+/// The response of the [/deploymentinfo](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#deployment-info) endpoint
+/// (Bitcoin Core >= 25.0).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DeploymentInfo {
+    /// The block hash the deployment states are evaluated at.
+    pub hash: String,
+    pub height: u32,
+    pub deployments: HashMap<String, Softfork>,
+}
+
+/// The `chain` field of `chaininfo`, without parsing the rest of the response.
+#[derive(Deserialize)]
+struct ChainName {
+    chain: String,
+}
+
+/// Map a chain name reported by `chaininfo` to a `bitcoin::Network`.
+fn network_from_chain(chain: &str) -> Result<Network, Error> {
+    match chain {
+        "main" => Ok(Network::Bitcoin),
+        "test" => Ok(Network::Testnet),
+        "signet" => Ok(Network::Signet),
+        "regtest" => Ok(Network::Regtest),
+        _ => Err(Error::UnknownChain(chain.to_string())),
+    }
+}
+
+impl ChainInfo {
+    /// The median time past of the tip as a `SystemTime`.
+    pub fn mediantime_datetime(&self) -> std::time::SystemTime {
+        std::time::UNIX_EPOCH + Duration::from_secs(self.mediantime as u64)
+    }
+    /// The tip block time as a `SystemTime`, or `None` if the node does not report it (Bitcoin Core < 23.0).
+    pub fn time_datetime(&self) -> Option<std::time::SystemTime> {
+        if self.time == 0 {
+            return None;
+        }
+        Some(std::time::UNIX_EPOCH + Duration::from_secs(self.time as u64))
+    }
+    /// The verification progress in percent, clamped to `0.0..=100.0`.
+    pub fn verification_percent(&self) -> f64 {
+        (self.verificationprogress * 100.0).clamp(0.0, 100.0)
+    }
+    /// Check whether the node is in the initial block download, as reported by Bitcoin Core.
+    pub fn in_ibd(&self) -> bool {
+        self.initialblockdownload
+    }
+    /// Check whether the block data at `height` is available on the node.
+    ///
+    /// Returns `false` if `height` is above the tip, or the node is pruned and `height` is below `pruneheight`.
+    pub fn is_available_at(&self, height: u32) -> bool {
+        height <= self.blocks && !(self.pruned && height < self.pruneheight)
+    }
+}
+
+fn deserialize_warnings<'de, D>(deserializer: D) -> Result<String, D::Error>
+    where D: serde::Deserializer<'de>
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Warnings {
+        String(String),
+        Array(Vec<String>),
+    }
+    Ok(match Warnings::deserialize(deserializer)? {
+        Warnings::String(warnings) => warnings,
+        Warnings::Array(warnings) => warnings.join("\n"),
+    })
+}
+
+fn deserialize_softforks<'de, D>(deserializer: D) -> Result<Option<HashMap<String, Softfork>>, D::Error>
+    where D: serde::Deserializer<'de>
+{
+    // Not an untagged enum, which cannot buffer numbers with the `arbitrary-precision` feature.
+    match serde_json::Value::deserialize(deserializer)? {
+        softforks @ serde_json::Value::Object(_) => serde_json::from_value(softforks).map(Some).map_err(serde::de::Error::custom),
+        _ => Ok(None),
+    }
+}
+
+/// (De)serialize an `Amount` as a BTC-denominated JSON number.
+///
+/// The amount is converted from/to the decimal representation of the number rather than an `f64`,
+/// so it is exact as long as serde_json keeps the digits, i.e. with the `arbitrary-precision` feature.
+mod btc_amount {
+    use std::str::FromStr;
+    use bitcoin::Amount;
+    use bitcoin::util::amount::Denomination;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    pub fn serialize<S: Serializer>(amount: &Amount, serializer: S) -> Result<S::Ok, S::Error> {
+        let number = serde_json::Number::from_str(&amount.to_string_in(Denomination::Bitcoin))
+            .map_err(serde::ser::Error::custom)?;
+        number.serialize(serializer)
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Amount, D::Error> {
+        let number = serde_json::Number::deserialize(deserializer)?;
+        match Amount::from_str_in(&number.to_string(), Denomination::Bitcoin) {
+            Ok(amount) => Ok(amount),
+            // e.g. an exponent notation.
+            Err(_) => {
+                let btc = number.as_f64().ok_or_else(|| serde::de::Error::custom("invalid amount"))?;
+                Amount::from_btc(btc).map_err(serde::de::Error::custom)
+            },
+        }
+    }
+}
+
+/// (De)serialize a `SignedAmount` as a BTC-denominated JSON number, the same way as `btc_amount`.
+mod btc_signed_amount {
+    use std::str::FromStr;
+    use bitcoin::SignedAmount;
+    use bitcoin::util::amount::Denomination;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    pub fn serialize<S: Serializer>(amount: &SignedAmount, serializer: S) -> Result<S::Ok, S::Error> {
+        let number = serde_json::Number::from_str(&amount.to_string_in(Denomination::Bitcoin))
+            .map_err(serde::ser::Error::custom)?;
+        number.serialize(serializer)
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SignedAmount, D::Error> {
+        let number = serde_json::Number::deserialize(deserializer)?;
+        match SignedAmount::from_str_in(&number.to_string(), Denomination::Bitcoin) {
+            Ok(amount) => Ok(amount),
+            // e.g. an exponent notation.
+            Err(_) => {
+                let btc = number.as_f64().ok_or_else(|| serde::de::Error::custom("invalid amount"))?;
+                SignedAmount::from_btc(btc).map_err(serde::de::Error::custom)
+            },
+        }
+    }
+}
+
+/// (De)serialize an optional `SignedAmount` as a satoshi-denominated JSON integer.
+mod opt_sat_signed_amount {
+    use bitcoin::SignedAmount;
+    use serde::{Deserialize, Deserializer, Serializer};
+    pub fn serialize<S: Serializer>(amount: &Option<SignedAmount>, serializer: S) -> Result<S::Ok, S::Error> {
+        match amount {
+            Some(amount) => serializer.serialize_some(&amount.as_sat()),
+            None => serializer.serialize_none(),
+        }
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<SignedAmount>, D::Error> {
+        Ok(Option::<i64>::deserialize(deserializer)?.map(SignedAmount::from_sat))
+    }
+}
+
+/// The `fees` object of a mempool entry.
+///
+/// All but `base` include the fee deltas set by `prioritisetransaction`, so they can be negative.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MempoolFees {
+    #[serde(with="btc_amount")]
+    pub base: Amount,
+    #[serde(with="btc_signed_amount")]
+    pub modified: SignedAmount,
+    #[serde(with="btc_signed_amount")]
+    pub ancestor: SignedAmount,
+    #[serde(with="btc_signed_amount")]
+    pub descendant: SignedAmount,
+}
+
+/// The response of the [/mempool/contents](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#memory-pool)
+/// endpoint requested with `verbose=false`, or the verbose one from nodes which ignore the parameter.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MempoolTxids {
+    Txids(Vec<String>),
+    Entries(HashMap<String, serde::de::IgnoredAny>),
+}
+
+impl MempoolTxids {
+    fn contains(&self, txid: &str) -> bool {
+        match self {
+            MempoolTxids::Txids(txids) => txids.iter().any(|t| t == txid),
+            MempoolTxids::Entries(entries) => entries.contains_key(txid),
+        }
+    }
+}
+
+/// The field of a `/tx` JSON response which tells whether the transaction is confirmed.
+#[derive(Deserialize)]
+struct TxBlockHash {
+    /// Only present for confirmed transactions.
+    #[serde(default)]
+    blockhash: Option<String>,
+}
+
+/// The field of a `/headers` JSON response used to count confirmations.
+#[derive(Deserialize)]
+struct HeaderConfirmations {
+    confirmations: i64,
+}
+
+/// Where [Context::probe_utxo_height](./struct.Context.html#method.probe_utxo_height) found a transaction.
+enum UtxoProbe {
+    Confirmed { height: u32, chain_height: u32 },
+    InMempool,
+    NotFound,
+}
+
+/// The response of the [/mempool/info](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#memory-pool) endpoint.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MempoolInfo {
+    #[serde(default)]
+    pub loaded: bool,
+    /// The number of transactions.
+    pub size: u64,
+    /// The sum of the transactions' virtual sizes.
+    pub bytes: u64,
+    /// The memory usage of the mempool.
+    pub usage: u64,
+    pub maxmempool: u64,
+    /// The minimum fee rate (per kvB) for a transaction to be accepted.
+    #[serde(with="btc_amount")]
+    pub mempoolminfee: Amount,
+    /// The minimum relay fee rate (per kvB).
+    #[serde(with="btc_amount")]
+    pub minrelaytxfee: Amount,
+}
+
+/// A verbose mempool entry.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MempoolEntry {
+    pub vsize: u32,
+    #[serde(default)]
+    pub weight: u32,
+    pub time: u64,
+    pub height: u32,
+    pub descendantcount: u32,
+    pub descendantsize: u32,
+    pub ancestorcount: u32,
+    pub ancestorsize: u32,
+    #[serde(default)]
+    pub wtxid: String,
+    pub fees: MempoolFees,
+    /// Legacy field in satoshis (Bitcoin Core < 23.0).
+    #[serde(default, with="opt_sat_signed_amount")]
+    pub ancestorfees: Option<SignedAmount>,
+    /// Legacy field in satoshis (Bitcoin Core < 23.0).
+    #[serde(default, with="opt_sat_signed_amount")]
+    pub descendantfees: Option<SignedAmount>,
+    #[serde(default)]
+    pub depends: Vec<String>,
+    #[serde(default)]
+    pub spentby: Vec<String>,
+    #[serde(default, rename="bip125-replaceable")]
+    pub bip125_replaceable: bool,
+    #[serde(default)]
+    pub unbroadcast: bool,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ScriptPubKey {
@@ -55,15 +366,20 @@ pub struct ScriptPubKey {
     pub req_sigs: u32,
     #[serde(rename="type")]
     pub type_: String,
+    /// Available on Bitcoin Core < 22.0.
     #[serde(default)]
     pub addresses: Vec<String>,
+    /// Available on Bitcoin Core >= 22.0.
+    #[serde(default)]
+    pub address: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Utxo {
     pub height: u32,
-    pub value: f64,
+    #[serde(with="btc_amount")]
+    pub value: Amount,
     pub script_pub_key: ScriptPubKey,
 }
 
@@ -76,15 +392,282 @@ pub struct UtxoData {
     pub utxos: Vec<Utxo>,
 }
 
+/// The difference between two [UtxoData](./struct.UtxoData.html) snapshots.
+///
+/// See [UtxoData::diff](./struct.UtxoData.html#method.diff).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtxoDiff {
+    /// Indices of the requested outpoints which are unspent in the older snapshot but not in the newer one.
+    pub spent: Vec<usize>,
+    /// Indices of the requested outpoints which are unspent in the newer snapshot but not in the older one.
+    pub created: Vec<usize>,
+    /// `true` if the two snapshots were taken at different chain tips.
+    pub tip_changed: bool,
+}
+
+impl UtxoData {
+    /// Compare this snapshot with a newer snapshot `other` of the same requested outpoints.
+    ///
+    /// Outpoints are identified by their index in the request.
+    /// If the two requests had different lengths, the missing entries are treated as not unspent.
+    pub fn diff(&self, other: &UtxoData) -> UtxoDiff {
+        let len = std::cmp::max(self.bitmap.len(), other.bitmap.len());
+        let unspent = |bitmap: &str, i: usize| bitmap.as_bytes().get(i) == Some(&b'1');
+        let mut spent = Vec::new();
+        let mut created = Vec::new();
+        for i in 0..len {
+            match (unspent(&self.bitmap, i), unspent(&other.bitmap, i)) {
+                (true, false) => spent.push(i),
+                (false, true) => created.push(i),
+                _ => {},
+            }
+        }
+        UtxoDiff {
+            spent,
+            created,
+            tip_changed: self.chaintip_hash != other.chaintip_hash,
+        }
+    }
+    /// Decode the binary [/getutxos](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#query-utxo-set) response.
+    ///
+    /// `outpoints` is the number of the requested outpoints, which is needed to render the bitmap.
+    /// The binary format only carries the raw script, so `script_pub_key` has only `hex` populated.
+    fn consensus_decode(mut d: &[u8], outpoints: usize) -> Result<Self, bitcoin::consensus::encode::Error> {
+        let chain_height = i32::consensus_decode(&mut d)?;
+        let chaintip_hash = BlockHash::consensus_decode(&mut d)?;
+        let bitmap = Vec::<u8>::consensus_decode(&mut d)?;
+        let len = VarInt::consensus_decode(&mut d)?.0;
+        let mut utxos = Vec::new();
+        for _ in 0..len {
+            // CCoin: a dummy transaction version, the height and the output.
+            let _version = u32::consensus_decode(&mut d)?;
+            let height = u32::consensus_decode(&mut d)?;
+            let txout = TxOut::consensus_decode(&mut d)?;
+            utxos.push(Utxo {
+                height,
+                value: Amount::from_sat(txout.value),
+                script_pub_key: ScriptPubKey {
+                    asm: String::new(),
+                    hex: txout.script_pubkey.as_bytes().to_hex(),
+                    req_sigs: 0,
+                    type_: String::new(),
+                    addresses: Vec::new(),
+                    address: None,
+                },
+            });
+        }
+        let bitmap = (0..outpoints).map(|i| {
+            match bitmap.get(i / 8) {
+                Some(byte) if byte & (1 << (i % 8)) != 0 => '1',
+                _ => '0',
+            }
+        }).collect();
+        Ok(UtxoData {
+            chain_height: chain_height as u32,
+            chaintip_hash: chaintip_hash.to_string(),
+            bitmap,
+            utxos,
+        })
+    }
+}
+
+/// The number of blocks between difficulty retargets under `params`,
+/// or `None` if it is zero, does not fit in a `u32` or the target spacing is zero.
+fn retarget_interval(params: &Params) -> Option<u32> {
+    let interval = params.pow_target_timespan.checked_div(params.pow_target_spacing)?;
+    u32::try_from(interval).ok().filter(|&interval| interval > 0)
+}
+
+/// An estimate of the next difficulty adjustment.
+///
+/// See [Context::difficulty_adjustment](./struct.Context.html#method.difficulty_adjustment).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DifficultyAdjustment {
+    /// The height of the first block of the current retarget window.
+    pub window_start_height: u32,
+    /// The height of the current chain tip.
+    pub tip_height: u32,
+    /// The number of blocks left until the next retarget.
+    pub remaining_blocks: u32,
+    /// The average block interval (in seconds) observed in the current window.
+    pub average_block_time: f64,
+    /// The projected difficulty change in percent (e.g. `2.5` means +2.5%).
+    pub difficulty_change_percent: f64,
+    /// The estimated UNIX timestamp of the next retarget.
+    pub estimated_retarget_time: u32,
+}
+
+impl DifficultyAdjustment {
+    /// Compute the estimate from the first header of the current retarget window and the tip header,
+    /// using the retarget interval and target spacing of `params`.
+    ///
+    /// While no block has been mined after the window start, the target spacing is assumed.
+    /// Returns `None` if `tip_height` is not within the window starting at `window_start_height`,
+    /// or if `params` has a zero target spacing or retarget interval.
+    pub fn new(params: &Params, window_start_height: u32, window_start: &BlockHeader, tip_height: u32, tip: &BlockHeader) -> Option<Self> {
+        let interval = retarget_interval(params)?;
+        let elapsed_blocks = tip_height.checked_sub(window_start_height)?;
+        let remaining_blocks = interval.checked_sub(elapsed_blocks).filter(|&remaining| remaining > 0)?;
+        let average_block_time = if elapsed_blocks == 0 {
+            params.pow_target_spacing as f64
+        } else {
+            (tip.time as f64 - window_start.time as f64) / elapsed_blocks as f64
+        };
+        let timespan = params.pow_target_timespan as f64;
+        // Core clamps the actual time span into [timespan / 4, timespan * 4].
+        let projected_timespan = (average_block_time * interval as f64).clamp(timespan / 4.0, timespan * 4.0);
+        let difficulty_change_percent = (timespan / projected_timespan - 1.0) * 100.0;
+        let estimated_retarget_time = (tip.time as f64 + average_block_time.max(0.0) * remaining_blocks as f64) as u32;
+        Some(DifficultyAdjustment {
+            window_start_height,
+            tip_height,
+            remaining_blocks,
+            average_block_time,
+            difficulty_change_percent,
+            estimated_retarget_time,
+        })
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
+    /// Any other HTTP client failure (e.g. a redirect loop).
     Reqwest(reqwest::Error),
-    BitcoinEncodeError(bitcoin::consensus::encode::Error)
+    /// Failed to connect to the node.
+    Connect(reqwest::Error),
+    /// The request timed out (including connect timeouts).
+    Timeout(reqwest::Error),
+    /// The connection failed while reading the response body.
+    BodyRead(reqwest::Error),
+    BitcoinEncodeError(bitcoin::consensus::encode::Error),
+    BitcoinHexError(bitcoin::hashes::hex::Error),
+    /// A sum of amounts exceeded `u64::MAX` satoshis.
+    AmountOverflow,
+    /// The overall deadline passed before all the requests completed.
+    DeadlineExceeded,
+    Io(std::io::Error),
+    /// Failed to decode the response for `path`.
+    ///
+    /// `source` is a `bitcoin::consensus::encode::Error` for binary responses and a `serde_json::Error` for JSON ones.
+    Decode {
+        path: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// The node responded to `path` with a non-success HTTP status.
+    Http {
+        path: String,
+        status: reqwest::StatusCode,
+        body: String,
+    },
+    /// The transaction at `path` was not found.
+    ///
+    /// Bitcoin Core gives the same answer whether the transaction does not exist or the node lacks `-txindex`,
+    /// which is needed to look up confirmed transactions.
+    TxNotFound {
+        path: String,
+    },
+    /// The header at `index` of a header sequence does not connect to the previous one.
+    HeaderChainMismatch {
+        index: usize,
+    },
+    /// The node reported a chain name which does not map to a `bitcoin::Network`.
+    UnknownChain(String),
+    /// The node does not serve the REST interface (it is not started with `-rest=1`).
+    RestDisabled,
+    /// The endpoint is not a valid URL.
+    InvalidEndpoint {
+        endpoint: String,
+        source: url::ParseError,
+    },
+    /// The node rejected a getutxos request with more than `max` outpoints.
+    TooManyOutpoints {
+        max: usize,
+    },
+    /// The transaction `txid` was not confirmed in time by
+    /// [Context::wait_for_confirmation](./struct.Context.html#method.wait_for_confirmation).
+    ConfirmationTimeout {
+        txid: Txid,
+        /// Whether the transaction was in the mempool at the last poll.
+        in_mempool: bool,
+    },
+    /// The node did not report the named deployment (softfork), or does not report deployments at all
+    /// (Bitcoin Core 23.0 and 24.x).
+    DeploymentNotFound(String),
+    /// The consensus params have a zero target spacing or retarget interval.
+    InvalidParams,
+    /// The retarget epoch containing `height` ends beyond the largest `u32` height.
+    EpochOutOfRange {
+        height: u32,
+    },
+}
+
+impl Error {
+    fn decode<E: std::error::Error + Send + Sync + 'static>(path: &str, source: E) -> Self {
+        Error::Decode {
+            path: path.to_string(),
+            source: Box::new(source),
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Reqwest(err) => write!(f, "HTTP request failed: {}", err),
+            Error::Connect(err) => write!(f, "failed to connect to the node: {}", err),
+            Error::Timeout(err) => write!(f, "HTTP request timed out: {}", err),
+            Error::BodyRead(err) => write!(f, "failed to read the response body: {}", err),
+            Error::BitcoinEncodeError(err) => write!(f, "failed to decode the response: {}", err),
+            Error::BitcoinHexError(err) => write!(f, "failed to parse hex: {}", err),
+            Error::AmountOverflow => write!(f, "amount overflow"),
+            Error::DeadlineExceeded => write!(f, "deadline exceeded"),
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::Decode { path, source } => write!(f, "failed to decode the response for {}: {}", path, source),
+            Error::Http { path, status, body } => write!(f, "HTTP error {} for {}: {}", status, path, body.trim_end()),
+            Error::TxNotFound { path } => write!(f,
+                "transaction not found for {}: the node may need to be started with -txindex=1 to look up transactions not in the mempool",
+                path,
+            ),
+            Error::HeaderChainMismatch { index } => write!(f, "header at index {} does not connect to the previous one", index),
+            Error::UnknownChain(chain) => write!(f, "unknown chain: {}", chain),
+            Error::RestDisabled => write!(f, "the REST interface is disabled: start the node with -rest=1"),
+            Error::TooManyOutpoints { max } => write!(f, "too many outpoints in a getutxos request (max: {})", max),
+            Error::InvalidEndpoint { endpoint, source } => write!(f, "invalid endpoint {}: {}", endpoint, source),
+            Error::ConfirmationTimeout { txid, in_mempool: true } => write!(f, "transaction {} is still unconfirmed in the mempool", txid),
+            Error::ConfirmationTimeout { txid, in_mempool: false } => write!(f, "transaction {} is neither confirmed nor in the mempool", txid),
+            Error::DeploymentNotFound(name) => write!(f, "deployment not reported by the node: {}", name),
+            Error::InvalidParams => write!(f, "invalid consensus params: zero target spacing or retarget interval"),
+            Error::EpochOutOfRange { height } => write!(f, "the retarget epoch containing height {} ends beyond the largest height", height),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Reqwest(err) | Error::Connect(err) | Error::Timeout(err) | Error::BodyRead(err) => Some(err),
+            Error::BitcoinEncodeError(err) => Some(err),
+            Error::Decode { source, .. } => Some(source.as_ref()),
+            Error::InvalidEndpoint { source, .. } => Some(source),
+            Error::BitcoinHexError(err) => Some(err),
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
 }
 
 impl From<reqwest::Error> for Error {
+    /// Classify `err` into `Timeout`, `Connect` or `BodyRead`, falling back to `Reqwest`.
     fn from(err: reqwest::Error) -> Self {
-        Self::Reqwest(err)
+        if err.is_timeout() {
+            Self::Timeout(err)
+        } else if err.is_connect() {
+            Self::Connect(err)
+        } else if err.is_body() {
+            Self::BodyRead(err)
+        } else {
+            Self::Reqwest(err)
+        }
     }
 }
 
@@ -94,174 +677,2714 @@ impl From<bitcoin::consensus::encode::Error> for Error {
     }
 }
 
-/// `bitcoin_rest` context.
-#[derive(Debug, Clone)]
-pub struct Context {
-    endpoint: String,
-    client: reqwest::Client,
+/// Sum up the values of `outputs` with overflow checks.
+fn sum_output_values<'a, I: IntoIterator<Item = &'a TxOut>>(outputs: I) -> Result<Amount, Error> {
+    outputs.into_iter().try_fold(Amount::ZERO, |sum, output| {
+        sum.checked_add(Amount::from_sat(output.value)).ok_or(Error::AmountOverflow)
+    })
 }
 
-/// Create a new `bitcoin_rest` context.
+/// Await `future`, giving up with `Error::DeadlineExceeded` after `deadline` (if any).
 ///
-/// The `endpoint` will be the string like "http://localhost:8332/rest"
-/// (Note: this string is available via `bitcoin_rest::DEFAULT_ENDPOINT`).
-pub fn new(endpoint: &str) -> Context {
-    Context {
-        endpoint: endpoint.to_string(),
-        client: reqwest::Client::new(),
+/// The future is dropped on timeout, which cancels all of its outstanding requests.
+async fn run_with_deadline<T, F: Future<Output = Result<T, Error>>>(deadline: Option<Duration>, future: F) -> Result<T, Error> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout(deadline, future).await.map_err(|_| Error::DeadlineExceeded)?,
+        None => future.await,
     }
 }
 
-impl Context {
-    /// Call the REST endpoint and parse it as a JSON.
-    pub async fn call_json<T: for<'de> Deserialize<'de>>(&self, path: &str) -> Result<T, reqwest::Error> {
-        let url = format!("{}/{}.json", &self.endpoint, path);
-        let result = self.client.get(url)
-            .send().await?
-            .json::<T>().await?;
-        Ok(result)
+/// Compute the outpoints created and spent by `block`.
+///
+/// See [Context::block_utxo_delta](./struct.Context.html#method.block_utxo_delta).
+pub fn utxo_delta(block: &Block) -> (Vec<OutPoint>, Vec<OutPoint>) {
+    let mut created = Vec::new();
+    let mut spent = Vec::new();
+    for tx in block.txdata.iter() {
+        if !tx.is_coin_base() {
+            spent.extend(tx.input.iter().map(|input| input.previous_output));
+        }
+        let txid = tx.txid();
+        for (vout, output) in tx.output.iter().enumerate() {
+            if !output.script_pubkey.is_provably_unspendable() {
+                created.push(OutPoint::new(txid, vout as u32));
+            }
+        }
     }
-    /// Call the REST endpoint (binary).
-    pub async fn call_bin(&self, path: &str) -> Result<bytes::Bytes, reqwest::Error> {
-        let url = format!("{}/{}.bin", &self.endpoint, path);
-        let result = self.client.get(url)
-            .send().await?
-            .bytes().await?;
-        Ok(result)
+    (created, spent)
+}
+
+/// List every output of `block` with its outpoint, in block order.
+///
+/// Unlike [utxo_delta](./fn.utxo_delta.html), provably unspendable outputs (e.g. `OP_RETURN`) are included,
+/// so the vout of an outpoint is always the index of the output in its transaction.
+///
+/// See [Context::block_outputs](./struct.Context.html#method.block_outputs).
+pub fn block_outputs(block: &Block) -> Vec<(OutPoint, TxOut)> {
+    let mut outputs = Vec::new();
+    for tx in block.txdata.iter() {
+        let txid = tx.txid();
+        outputs.extend(tx.output.iter().enumerate()
+            .map(|(vout, output)| (OutPoint::new(txid, vout as u32), output.clone())));
     }
-    /// Call the REST endpoint (hex).
-    pub async fn call_hex(&self, path: &str) -> Result<String, reqwest::Error> {
-        let url = format!("{}/{}.hex", &self.endpoint, path);
-        let mut result = self.client.get(url)
-            .send().await?
-            .text().await?;
-        // Trim last '\n'.
-        result.pop();
-        Ok(result)
+    outputs
+}
+
+/// Get the coinbase scriptSig bytes of `block`, where miners put arbitrary data.
+///
+/// Returns an empty vector if the block has no transactions.
+pub fn coinbase_message(block: &Block) -> Vec<u8> {
+    block.txdata.first()
+        .and_then(|coinbase| coinbase.input.first())
+        .map(|input| input.script_sig.to_bytes())
+        .unwrap_or_default()
+}
+
+/// Parse the block height encoded at the beginning of the coinbase scriptSig of `block` (BIP34).
+///
+/// Returns `None` for version 1 blocks, which predate BIP34, or if the scriptSig does not start with a height push.
+/// Note that some early version 2 blocks mined before BIP34 was enforced do not encode the height,
+/// so their result may be wrong.
+pub fn coinbase_height(block: &Block) -> Option<u32> {
+    if block.header.version < 2 {
+        return None;
     }
-    /// Call the [/tx](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#transactions) endpoint.
-    pub async fn tx(&self, txhash: &Txid) -> Result<Transaction, Error> {
-        let result = self.call_bin(&["tx", &txhash.to_string()].join("/")).await?;
-        Ok(Transaction::consensus_decode(result.as_ref())?)
+    let script = coinbase_message(block);
+    let (&opcode, rest) = script.split_first()?;
+    match opcode {
+        // OP_1..OP_16 for heights 1-16 (e.g. on regtest).
+        0x51..=0x60 => Some((opcode - 0x50) as u32),
+        // A little-endian, sign-magnitude number of 1 to 4 bytes.
+        0x01..=0x04 => {
+            let bytes = rest.get(..opcode as usize)?;
+            if bytes[bytes.len() - 1] & 0x80 != 0 {
+                return None;
+            }
+            Some(bytes.iter().rev().fold(0, |height, &byte| height << 8 | byte as u32))
+        },
+        _ => None,
     }
-    /// Call the [/block](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#blocks) endpoint.
-    pub async fn block(&self, blockhash: &BlockHash) -> Result<Block, Error> {
-        let result = self.call_bin(&["block", &blockhash.to_string()].join("/")).await?;
-        Ok(Block::consensus_decode(result.as_ref())?)
+}
+
+/// A snapshot of the node's chain and mempool state, e.g. for monitoring.
+#[derive(Debug, Clone)]
+pub struct NodeStatus {
+    pub height: u32,
+    pub bestblockhash: BlockHash,
+    pub initialblockdownload: bool,
+    pub verificationprogress: f64,
+    /// The number of transactions in the mempool.
+    pub mempool_size: u64,
+    /// The sum of the virtual sizes of the mempool transactions.
+    pub mempool_bytes: u64,
+    /// The minimum fee rate (per kvB) accepted by the mempool.
+    pub mempool_minfee: Amount,
+}
+
+impl NodeStatus {
+    /// Call the [/chaininfo](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#chaininfo)
+    /// and [/mempool/info](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#memory-pool) endpoints concurrently
+    /// and bundle the results.
+    pub async fn fetch(context: &Context) -> Result<Self, Error> {
+        let (chaininfo, mempool_info) = futures::try_join!(context.chaininfo(), context.mempool_info())?;
+        Ok(NodeStatus {
+            height: chaininfo.blocks,
+            bestblockhash: chaininfo.bestblockhash.parse::<BlockHash>()?,
+            initialblockdownload: chaininfo.in_ibd(),
+            verificationprogress: chaininfo.verificationprogress,
+            mempool_size: mempool_info.size,
+            mempool_bytes: mempool_info.bytes,
+            mempool_minfee: mempool_info.mempoolminfee,
+        })
     }
-    /// Call the [/block/notxdetails](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#blocks) endpoint.
-    pub async fn block_notxdetails(&self, blockhash: &BlockHash) -> Result<BlockHeader, Error> {
-        let result = self.call_bin(&["block", "notxdetails", &blockhash.to_string()].join("/")).await?;
-        Ok(BlockHeader::consensus_decode(result.as_ref())?)
+}
+
+/// The result of [Context::export_blocks](./struct.Context.html#method.export_blocks).
+#[derive(Debug, Default)]
+pub struct ExportSummary {
+    /// The number of blocks sent to the channel.
+    pub exported: u32,
+    /// The heights which failed to be fetched, with the errors.
+    pub failed: Vec<(u32, Error)>,
+    /// `true` if the export stopped early because the receiver was dropped.
+    pub receiver_closed: bool,
+}
+
+impl ExportSummary {
+    /// Check whether all the blocks were exported.
+    pub fn is_ok(&self) -> bool {
+        self.failed.is_empty() && !self.receiver_closed
     }
-    /// Call the [/headers](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#blockheaders) endpoint.
-    pub async fn headers(&self, count: u32, blockhash: &BlockHash) -> Result<Vec<BlockHeader>, Error> {
-        let result = self.call_bin(&["headers", &count.to_string(), &blockhash.to_string()].join("/")).await?;
-        let mut ret = Vec::new();
-        const BLOCK_HEADER_SIZE: usize = 80usize;
-        let mut offset = 0;
-        while offset < result.len() {
-            ret.push(BlockHeader::consensus_decode(result[offset..(offset+BLOCK_HEADER_SIZE)].as_ref())?);
-            offset += BLOCK_HEADER_SIZE;
+}
+
+/// The direction of [Context::headers_directional](./struct.Context.html#method.headers_directional).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Towards the tip (increasing height).
+    Forward,
+    /// Towards genesis (decreasing height).
+    Backward,
+}
+
+/// The result of [Context::block_integrity](./struct.Context.html#method.block_integrity).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockIntegrity {
+    /// The hash of the block equals the requested one.
+    pub hash_matches: bool,
+    /// The merkle root computed from the transactions equals the one in the header.
+    pub merkle_root_matches: bool,
+    /// The block hash meets the target in the header (see [check_pow](./fn.check_pow.html)).
+    pub pow_valid: bool,
+}
+
+impl BlockIntegrity {
+    /// Run all the checks on `block`, which is expected to have the hash `blockhash`.
+    pub fn check(block: &Block, blockhash: &BlockHash) -> Self {
+        BlockIntegrity {
+            hash_matches: block.block_hash() == *blockhash,
+            merkle_root_matches: block.check_merkle_root(),
+            pow_valid: check_pow(&block.header),
         }
-        Ok(ret)
     }
-    /// Call the [/blockhashbyheight](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#blockhash-by-height) endpoint.
-    pub async fn blockhashbyheight(&self, height: u32) -> Result<BlockHash, Error> {
-        let result = self.call_bin(&["blockhashbyheight", &height.to_string()].join("/")).await?;
-        Ok(BlockHash::consensus_decode(result.as_ref())?)
+    /// Check whether all the checks passed.
+    pub fn is_ok(&self) -> bool {
+        self.hash_matches && self.merkle_root_matches && self.pow_valid
     }
-    /// Call the [/chaininfo](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#chaininfo) endpoint.
-    pub async fn chaininfo(&self) -> Result<ChainInfo, Error> {
-        let result: ChainInfo = self.call_json("chaininfo").await?;
-        Ok(result)
+}
+
+/// Extract the [BIP141](https://github.com/bitcoin/bips/blob/master/bip-0141.mediawiki#commitment-structure)
+/// witness commitment from the coinbase outputs of `block`.
+///
+/// The commitment output is `OP_RETURN` followed by a 36-byte push starting with `0xaa21a9ed`.
+/// If multiple outputs match, the one with the highest index is used, as specified by BIP141.
+/// Returns `None` if there is no such output (e.g. pre-segwit blocks).
+pub fn witness_commitment(block: &Block) -> Option<[u8; 32]> {
+    const HEADER: [u8; 6] = [0x6a, 0x24, 0xaa, 0x21, 0xa9, 0xed];
+    let coinbase = block.txdata.first()?;
+    coinbase.output.iter().rev()
+        .map(|output| output.script_pubkey.as_bytes())
+        .find(|script| script.len() >= 38 && script[0..6] == HEADER)
+        .map(|script| {
+            let mut commitment = [0u8; 32];
+            commitment.copy_from_slice(&script[6..38]);
+            commitment
+        })
+}
+
+/// The block subsidy (newly minted coins) at `height`: 50 BTC halved every `halving_interval` blocks.
+///
+/// See [subsidy_halving_interval](./fn.subsidy_halving_interval.html) for the interval of each network.
+pub fn block_subsidy(height: u32, halving_interval: u32) -> Amount {
+    let halvings = height / halving_interval.max(1);
+    if halvings >= 64 {
+        return Amount::ZERO;
     }
-    /// Call the [/getutxos](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#query-utxo-set) endpoint.
-    pub async fn getutxos(&self, checkmempool: bool, txids: &[Txid]) -> Result<UtxoData, Error> {
-        let mut path = Vec::with_capacity(1 + if checkmempool { 0 } else { 1 } + txids.len());
-        path.push("getutxos".to_string());
-        if checkmempool {
-            path.push("checkmempool".to_string());
-        }
-        for (i, txid) in txids.iter().enumerate() {
-            path.push([txid.to_string(), i.to_string()].join("-"));
+    Amount::from_sat((50 * 100_000_000) >> halvings)
+}
+
+/// Check that the hash of `header` meets the target encoded in its `bits`.
+///
+/// This does not check that `bits` itself is the correct target for the block's height.
+pub fn check_pow(header: &BlockHeader) -> bool {
+    header.validate_pow(&header.target()).is_ok()
+}
+
+/// Verify that `headers` form a chain building on `prev_blockhash`.
+///
+/// Returns `Error::HeaderChainMismatch` with the index of the first header whose `prev_blockhash`
+/// does not match the hash of the previous header (or `prev_blockhash` for the first one).
+pub fn verify_header_chain(headers: &[BlockHeader], prev_blockhash: &BlockHash) -> Result<(), Error> {
+    let mut prev = *prev_blockhash;
+    for (index, header) in headers.iter().enumerate() {
+        if header.prev_blockhash != prev {
+            return Err(Error::HeaderChainMismatch { index });
         }
-        let result: UtxoData = self.call_json(&path.join("/")).await?;
-        Ok(result)
+        prev = header.block_hash();
     }
+    Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use std::str::FromStr;
-    use super::*;
-    #[tokio::test]
-    async fn reqwest_fail() {
-        let rest = new("http://invalid-url");
-        assert!(rest.blockhashbyheight(0).await.is_err());
-    }
-    struct Fixture {
-        rest_env_name: &'static str,
-        genesis_block_hash: &'static str,
-        txid_coinbase_block1: &'static str,
+/// The size of a serialized block header.
+const BLOCK_HEADER_SIZE: usize = 80;
+
+/// An iterator decoding block headers from a [/headers](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#blockheaders) response.
+///
+/// See [Context::headers_iter](./struct.Context.html#method.headers_iter).
+#[derive(Debug, Clone)]
+pub struct HeadersIter {
+    path: String,
+    bytes: bytes::Bytes,
+    offset: usize,
+}
+
+impl Iterator for HeadersIter {
+    type Item = Result<BlockHeader, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.bytes.len() {
+            return None;
+        }
+        let end = std::cmp::min(self.offset + BLOCK_HEADER_SIZE, self.bytes.len());
+        let result = BlockHeader::consensus_decode(self.bytes[self.offset..end].as_ref());
+        self.offset = match result {
+            Ok(_) => end,
+            // Stop iterating after a decode failure.
+            Err(_) => self.bytes.len(),
+        };
+        Some(result.map_err(|err| Error::decode(&self.path, err)))
     }
-    async fn decode_fail(f: &Fixture) {
-        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
-        let rest = new(&test_endpoint);
-        assert!(rest.blockhashbyheight(0xFFFFFFFF).await.is_err());
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.bytes.len() - self.offset + BLOCK_HEADER_SIZE - 1) / BLOCK_HEADER_SIZE;
+        (remaining, Some(remaining))
     }
-    async fn tx(f: &Fixture) {
-        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
-        let rest = new(&test_endpoint);
-        let tx = rest.tx(&Txid::from_str(f.txid_coinbase_block1).unwrap()).await.unwrap();
-        assert_eq!(tx.txid().to_string(), f.txid_coinbase_block1);
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
     }
-    async fn block(f: &Fixture) {
-        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
-        let rest = new(&test_endpoint);
-        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
-        let block = rest.block(&blockid).await.unwrap();
-        assert_eq!(block.block_hash().to_string(), f.genesis_block_hash);
+}
+
+impl From<bitcoin::hashes::hex::Error> for Error {
+    fn from(err: bitcoin::hashes::hex::Error) -> Self {
+        Self::BitcoinHexError(err)
     }
-    async fn block_notxdetails(f: &Fixture) {
-        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
-        let rest = new(&test_endpoint);
-        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
-        let blockheader = rest.block_notxdetails(&blockid).await.unwrap();
-        assert_eq!(blockheader.block_hash().to_string(), f.genesis_block_hash);
+}
+
+/// Header-level information of a block, suitable as a chain index entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockSummary {
+    pub hash: BlockHash,
+    pub prev_blockhash: BlockHash,
+    pub merkle_root: bitcoin::hash_types::TxMerkleNode,
+    pub time: u32,
+    pub bits: u32,
+    pub nonce: u32,
+    pub tx_count: usize,
+    /// The block weight as defined in BIP141.
+    pub weight: usize,
+}
+
+impl From<&Block> for BlockSummary {
+    fn from(block: &Block) -> Self {
+        let header_weight = (BLOCK_HEADER_SIZE + VarInt(block.txdata.len() as u64).len()) * 4;
+        BlockSummary {
+            hash: block.block_hash(),
+            prev_blockhash: block.header.prev_blockhash,
+            merkle_root: block.header.merkle_root,
+            time: block.header.time,
+            bits: block.header.bits,
+            nonce: block.header.nonce,
+            tx_count: block.txdata.len(),
+            weight: header_weight + block.txdata.iter().map(Transaction::get_weight).sum::<usize>(),
+        }
     }
-    async fn headers(f: &Fixture) {
-        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
-        let rest = new(&test_endpoint);
-        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
-        let headers = rest.headers(1, &blockid).await.unwrap();
-        assert_eq!(headers[0].block_hash().to_string(), f.genesis_block_hash);
+}
+
+/// A transaction sent in full in a [HeaderAndShortIds](./struct.HeaderAndShortIds.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefilledTransaction {
+    /// The (absolute) index of the transaction in the block.
+    pub index: u16,
+    pub tx: Transaction,
+}
+
+/// A [BIP152](https://github.com/bitcoin/bips/blob/master/bip-0152.mediawiki) compact block
+/// (the payload of the `cmpctblock` message).
+///
+/// Short ids are derived from wtxids (compact block version 2) and only the coinbase is prefilled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeaderAndShortIds {
+    pub header: BlockHeader,
+    pub nonce: u64,
+    pub short_ids: Vec<[u8; 6]>,
+    pub prefilled_txs: Vec<PrefilledTransaction>,
+}
+
+impl HeaderAndShortIds {
+    /// Build a compact block from `block` with the short id salt `nonce`.
+    pub fn from_block(block: &Block, nonce: u64) -> Self {
+        use bitcoin::hashes::{sha256, siphash24};
+        let mut key_data = bitcoin::consensus::serialize(&block.header);
+        key_data.extend(bitcoin::consensus::serialize(&nonce));
+        let key = sha256::Hash::hash(&key_data).into_inner();
+        let mut k0 = [0u8; 8];
+        let mut k1 = [0u8; 8];
+        k0.copy_from_slice(&key[0..8]);
+        k1.copy_from_slice(&key[8..16]);
+        let (k0, k1) = (u64::from_le_bytes(k0), u64::from_le_bytes(k1));
+        let short_ids = block.txdata.iter().skip(1).map(|tx| {
+            let short_id = siphash24::Hash::hash_to_u64_with_keys(k0, k1, &tx.wtxid()[..]).to_le_bytes();
+            let mut ret = [0u8; 6];
+            ret.copy_from_slice(&short_id[0..6]);
+            ret
+        }).collect();
+        let prefilled_txs = block.txdata.iter().take(1).map(|tx| PrefilledTransaction {
+            index: 0,
+            tx: tx.clone(),
+        }).collect();
+        HeaderAndShortIds {
+            header: block.header,
+            nonce,
+            short_ids,
+            prefilled_txs,
+        }
+    }
+}
+
+impl Encodable for HeaderAndShortIds {
+    fn consensus_encode<S: std::io::Write>(&self, mut s: S) -> Result<usize, std::io::Error> {
+        let mut len = self.header.consensus_encode(&mut s)?;
+        len += self.nonce.consensus_encode(&mut s)?;
+        len += VarInt(self.short_ids.len() as u64).consensus_encode(&mut s)?;
+        for short_id in self.short_ids.iter() {
+            s.write_all(short_id)?;
+            len += short_id.len();
+        }
+        len += VarInt(self.prefilled_txs.len() as u64).consensus_encode(&mut s)?;
+        // Indices are differentially encoded.
+        let mut next_index = 0;
+        for prefilled in self.prefilled_txs.iter() {
+            len += VarInt((prefilled.index - next_index) as u64).consensus_encode(&mut s)?;
+            len += prefilled.tx.consensus_encode(&mut s)?;
+            next_index = prefilled.index + 1;
+        }
+        Ok(len)
+    }
+}
+
+/// A predicate deciding whether to retry a failed request, given the error and the attempt number (starting from 1).
+#[derive(Clone)]
+struct RetryPredicate(std::sync::Arc<dyn Fn(&Error, u32) -> bool + Send + Sync>);
+
+impl std::fmt::Debug for RetryPredicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RetryPredicate")
+    }
+}
+
+/// The base delay of [Builder::retry_backoff](./struct.Builder.html#method.retry_backoff) used if none is given.
+const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// The retry predicate used if none is given: retry transport errors and HTTP 5xx responses.
+fn default_retry_if(err: &Error, _attempt: u32) -> bool {
+    match err {
+        Error::Reqwest(_) | Error::Connect(_) | Error::Timeout(_) | Error::BodyRead(_) => true,
+        Error::Http { status, .. } => status.is_server_error(),
+        _ => false,
+    }
+}
+
+/// `bitcoin_rest` context.
+#[derive(Debug, Clone)]
+pub struct Context {
+    /// The endpoint, ending with a slash so that paths can be joined to it.
+    base_url: url::Url,
+    /// The endpoint and the parse error if the deprecated [new](./fn.new.html) was given a malformed endpoint.
+    invalid_endpoint: Option<(String, url::ParseError)>,
+    client: reqwest::Client,
+    #[cfg(feature="test-record")]
+    cassette: Option<cassette::Cassette>,
+    params: Option<Params>,
+    subsidy_halving_interval: Option<u32>,
+    retries: u32,
+    retry_if: Option<RetryPredicate>,
+    retry_backoff: Duration,
+    /// Set by [Builder::max_concurrent_requests](./struct.Builder.html#method.max_concurrent_requests), shared among the clones.
+    semaphore: Option<std::sync::Arc<tokio::sync::Semaphore>>,
+    /// Set by [with_deadline](#method.with_deadline).
+    deadline: Option<tokio::time::Instant>,
+    /// Cache of [network](#method.network), shared among the clones.
+    network: std::sync::Arc<tokio::sync::OnceCell<Network>>,
+    /// Cache of [genesis_hash](#method.genesis_hash), shared among the clones.
+    genesis_hash: std::sync::Arc<tokio::sync::OnceCell<BlockHash>>,
+}
+
+/// Create a new `bitcoin_rest` context.
+///
+/// The `endpoint` will be the string like "http://localhost:8332/rest"
+/// (Note: this string is available via `bitcoin_rest::DEFAULT_ENDPOINT`).
+///
+/// With the `test-record` feature, the cassette configured by the environment is used
+/// (see [cassette::Cassette::from_env](./cassette/struct.Cassette.html#method.from_env)).
+///
+/// Returns `Error::InvalidEndpoint` if `endpoint` is not a valid URL.
+pub fn try_new(endpoint: &str) -> Result<Context, Error> {
+    Ok(Context::with_client(parse_endpoint(endpoint)?, reqwest::Client::new()))
+}
+
+/// Create a new `bitcoin_rest` context without validating the endpoint up front.
+///
+/// This never fails: if `endpoint` is not a valid URL, every request returns `Error::InvalidEndpoint`
+/// and [Context::base_url](./struct.Context.html#method.base_url) is a placeholder.
+#[deprecated(since = "0.1.2", note = "use try_new, builder or connect, which reject a malformed endpoint")]
+pub fn new(endpoint: &str) -> Context {
+    match parse_endpoint(endpoint) {
+        Ok(base_url) => Context::with_client(base_url, reqwest::Client::new()),
+        Err(err) => {
+            let mut context = Context::with_client(url::Url::parse("http://invalid./").unwrap(), reqwest::Client::new());
+            if let Error::InvalidEndpoint { endpoint, source } = err {
+                context.invalid_endpoint = Some((endpoint, source));
+            }
+            context
+        },
+    }
+}
+
+/// Parse `endpoint` into a URL which paths can be joined to, appending a slash if missing.
+fn parse_endpoint(endpoint: &str) -> Result<url::Url, Error> {
+    let invalid = |source| Error::InvalidEndpoint {
+        endpoint: endpoint.to_string(),
+        source,
+    };
+    let mut url = url::Url::parse(endpoint).map_err(invalid)?;
+    if url.cannot_be_a_base() {
+        return Err(invalid(url::ParseError::RelativeUrlWithCannotBeABaseBase));
+    }
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
+    }
+    Ok(url)
+}
+
+/// Create a [Builder](./struct.Builder.html) to configure a `bitcoin_rest` context.
+///
+/// The `endpoint` is the same as [try_new](./fn.try_new.html).
+pub fn builder(endpoint: &str) -> Builder {
+    Builder {
+        endpoint: endpoint.to_string(),
+        client: reqwest::Client::builder(),
+        params: None,
+        retries: 0,
+        retry_if: None,
+        retry_backoff: DEFAULT_RETRY_BACKOFF,
+        max_concurrent_requests: None,
+        subsidy_halving_interval: None,
+    }
+}
+
+/// A builder of [Context](./struct.Context.html) exposing the HTTP client settings.
+#[derive(Debug)]
+pub struct Builder {
+    endpoint: String,
+    client: reqwest::ClientBuilder,
+    params: Option<Params>,
+    retries: u32,
+    retry_if: Option<RetryPredicate>,
+    retry_backoff: Duration,
+    max_concurrent_requests: Option<usize>,
+    subsidy_halving_interval: Option<u32>,
+}
+
+impl Builder {
+    /// Set `TCP_NODELAY` (i.e. disable Nagle's algorithm) on the connections.
+    ///
+    /// See [reqwest::ClientBuilder::tcp_nodelay](https://docs.rs/reqwest/0.11/reqwest/struct.ClientBuilder.html#method.tcp_nodelay).
+    pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+        self.client = self.client.tcp_nodelay(enabled);
+        self
+    }
+    /// Set how long idle connections are kept in the pool (`None` to keep them forever).
+    ///
+    /// See [reqwest::ClientBuilder::pool_idle_timeout](https://docs.rs/reqwest/0.11/reqwest/struct.ClientBuilder.html#method.pool_idle_timeout).
+    pub fn pool_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.client = self.client.pool_idle_timeout(timeout);
+        self
+    }
+    /// Set the maximum number of idle connections kept in the pool for the node.
+    ///
+    /// See [reqwest::ClientBuilder::pool_max_idle_per_host](https://docs.rs/reqwest/0.11/reqwest/struct.ClientBuilder.html#method.pool_max_idle_per_host).
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.client = self.client.pool_max_idle_per_host(max);
+        self
+    }
+    /// Resolve `domain` to `addr` instead of querying DNS, e.g. to pin the node's hostname to a specific IP.
+    ///
+    /// The port of `addr` is ignored; the one in the endpoint is used.
+    /// See [reqwest::ClientBuilder::resolve](https://docs.rs/reqwest/0.11/reqwest/struct.ClientBuilder.html#method.resolve).
+    pub fn resolve(mut self, domain: &str, addr: std::net::SocketAddr) -> Self {
+        self.client = self.client.resolve(domain, addr);
+        self
+    }
+    /// Use `params` as the consensus parameters instead of deriving them from the node's chain.
+    ///
+    /// This is needed for altcoin forks whose parameters differ from Bitcoin's.
+    pub fn params(mut self, params: Params) -> Self {
+        self.params = Some(params);
+        self
+    }
+    /// Retry a failed request up to `retries` times (default: `0`).
+    ///
+    /// Which failures are retried is decided by [retry_if](#method.retry_if).
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+    /// Wait `base * 2^(n - 1)` before the `n`-th retry (default: 100ms).
+    ///
+    /// The delay is capped at 2^10 times `base`. Waiting counts towards the deadline set by
+    /// [Context::with_deadline](./struct.Context.html#method.with_deadline).
+    pub fn retry_backoff(mut self, base: Duration) -> Self {
+        self.retry_backoff = base;
+        self
+    }
+    /// Decide whether to retry a failure with `predicate`, which takes the error and the attempt number (starting from 1).
+    ///
+    /// This only has effect together with [retries](#method.retries).
+    /// By default, transport errors and HTTP 5xx responses are retried.
+    pub fn retry_if<F: Fn(&Error, u32) -> bool + Send + Sync + 'static>(mut self, predicate: F) -> Self {
+        self.retry_if = Some(RetryPredicate(std::sync::Arc::new(predicate)));
+        self
+    }
+    /// Limit the number of requests in flight to `max` across the context and all its clones (default: unlimited).
+    ///
+    /// Every request waits for a free slot before being sent and holds it until its body is read.
+    /// This applies on top of the `concurrency` arguments of the batch helpers such as [Context::blocks](./struct.Context.html#method.blocks):
+    /// the smaller of the two effectively wins, while this limit also covers concurrent calls from separate tasks.
+    /// `0` is treated as `1`.
+    pub fn max_concurrent_requests(mut self, max: usize) -> Self {
+        self.max_concurrent_requests = Some(max.max(1));
+        self
+    }
+    /// Override the number of blocks between block subsidy halvings (e.g. `1_051_200` for Monacoin).
+    ///
+    /// rust-bitcoin's `Params` has no such field, so [params](#method.params) cannot change it.
+    /// By default, it is derived from the node's network (see [subsidy_halving_interval](./fn.subsidy_halving_interval.html)).
+    /// `0` is treated as `1`.
+    pub fn subsidy_halving_interval(mut self, interval: u32) -> Self {
+        self.subsidy_halving_interval = Some(interval.max(1));
+        self
+    }
+    /// Build the context.
+    ///
+    /// Returns `Error::InvalidEndpoint` if the endpoint is not a valid URL.
+    pub fn build(self) -> Result<Context, Error> {
+        let mut context = Context::with_client(parse_endpoint(&self.endpoint)?, self.client.build()?);
+        context.params = self.params;
+        context.subsidy_halving_interval = self.subsidy_halving_interval;
+        context.retries = self.retries;
+        context.retry_if = self.retry_if;
+        context.retry_backoff = self.retry_backoff;
+        context.semaphore = self.max_concurrent_requests
+            .map(|max| std::sync::Arc::new(tokio::sync::Semaphore::new(max)));
+        Ok(context)
+    }
+}
+
+/// Create a new `bitcoin_rest` context and check that the endpoint is reachable.
+///
+/// Unlike [try_new](./fn.try_new.html), which never touches the network, this calls the
+/// [/chaininfo](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#chaininfo) endpoint once
+/// and returns an error if the endpoint is malformed, or the node is unreachable or does not serve the REST interface.
+pub async fn connect(endpoint: &str) -> Result<Context, Error> {
+    let context = builder(endpoint).build()?;
+    context.chaininfo().await?;
+    Ok(context)
+}
+
+impl Context {
+    fn with_client(base_url: url::Url, client: reqwest::Client) -> Self {
+        Context {
+            base_url,
+            invalid_endpoint: None,
+            client,
+            #[cfg(feature="test-record")]
+            cassette: cassette::Cassette::from_env(),
+            params: None,
+            subsidy_halving_interval: None,
+            retries: 0,
+            retry_if: None,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+            semaphore: None,
+            deadline: None,
+            network: Default::default(),
+            genesis_hash: Default::default(),
+        }
+    }
+    /// The endpoint URL given at construction, with a trailing slash appended if missing.
+    pub fn base_url(&self) -> &url::Url {
+        &self.base_url
+    }
+    /// Record or replay the HTTP interactions with `cassette` (`None` to disable).
+    #[cfg(feature="test-record")]
+    pub fn with_cassette(mut self, cassette: Option<cassette::Cassette>) -> Self {
+        self.cassette = cassette;
+        self
+    }
+    /// Return a handle to the same node whose requests all have to complete by `deadline`.
+    ///
+    /// Once `deadline` passes, every request made through the handle (including retries)
+    /// fails with `Error::DeadlineExceeded`; a request in flight is cancelled.
+    /// This is useful to share an overall time budget across several calls.
+    /// If `self` already has an earlier deadline, it is kept.
+    pub fn with_deadline(&self, deadline: std::time::Instant) -> Self {
+        let deadline = tokio::time::Instant::from_std(deadline);
+        let mut context = self.clone();
+        context.deadline = Some(match self.deadline {
+            Some(current) => current.min(deadline),
+            None => deadline,
+        });
+        context
+    }
+    /// Run `future` until the deadline set by [with_deadline](#method.with_deadline), if any.
+    async fn within_deadline<T, F: Future<Output = Result<T, Error>>>(&self, future: F) -> Result<T, Error> {
+        match self.deadline {
+            Some(deadline) => {
+                if tokio::time::Instant::now() >= deadline {
+                    return Err(Error::DeadlineExceeded);
+                }
+                tokio::time::timeout_at(deadline, future).await.map_err(|_| Error::DeadlineExceeded)?
+            },
+            None => future.await,
+        }
+    }
+    /// Wait for a free slot if [Builder::max_concurrent_requests](./struct.Builder.html#method.max_concurrent_requests) is set.
+    ///
+    /// The slot is released when the returned permit is dropped.
+    async fn acquire(&self) -> Option<tokio::sync::SemaphorePermit<'_>> {
+        match &self.semaphore {
+            // The semaphore is never closed.
+            Some(semaphore) => semaphore.acquire().await.ok(),
+            None => None,
+        }
+    }
+    /// Turn a non-success response for `path` into an error.
+    ///
+    /// Bitcoin Core answers a missing transaction with 404 and "<txid> not found" regardless of `-txindex`,
+    /// which is returned as `Error::TxNotFound`.
+    /// REST errors always come with a message, while a node answers an unknown path with 404 and an empty body.
+    /// The latter is reported as `Error::RestDisabled` only for `chaininfo`, which every REST-enabled node serves;
+    /// for other paths it is returned as `Error::Http` and [get](#method.get) decides.
+    async fn check_status(path: &str, response: reqwest::Response) -> Result<reqwest::Response, Error> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let body = response.text().await?;
+        if status == reqwest::StatusCode::NOT_FOUND && path == "chaininfo" && body.trim().is_empty() {
+            return Err(Error::RestDisabled);
+        }
+        if status == reqwest::StatusCode::NOT_FOUND && path.starts_with("tx/") && body.contains("not found") {
+            return Err(Error::TxNotFound { path: path.to_string() });
+        }
+        if status == reqwest::StatusCode::BAD_REQUEST && path.starts_with("getutxos/") && body.contains("max outpoints exceeded") {
+            // "Error: max outpoints exceeded (max: 15, tried: 16)"
+            let max = body.split("max: ").nth(1)
+                .and_then(|rest| rest.split(|c: char| !c.is_ascii_digit()).next())
+                .and_then(|max| max.parse().ok())
+                .unwrap_or(MAX_GETUTXOS_OUTPOINTS);
+            return Err(Error::TooManyOutpoints { max });
+        }
+        Err(Error::Http { path: path.to_string(), status, body })
+    }
+    /// Send a GET request for `path` to `url`.
+    ///
+    /// A 404 with an empty body means either that the REST interface is disabled or that the node does not know
+    /// the endpoint (e.g. one added in a later version). In that case `chaininfo` is probed once to tell them apart:
+    /// `Error::RestDisabled` is returned if it is missing as well, otherwise the original `Error::Http`.
+    async fn get(&self, path: &str, url: String) -> Result<reqwest::Response, Error> {
+        let result = self.send(path, url).await;
+        if let Err(Error::Http { status: reqwest::StatusCode::NOT_FOUND, body, .. }) = &result {
+            if body.trim().is_empty() {
+                if let Err(Error::RestDisabled) = self.send("chaininfo", self.url("chaininfo", "json")?).await {
+                    return Err(Error::RestDisabled);
+                }
+            }
+        }
+        result
+    }
+    /// Send a GET request for `path` to `url` and check the status.
+    async fn send(&self, path: &str, url: String) -> Result<reqwest::Response, Error> {
+        if let Some((endpoint, source)) = &self.invalid_endpoint {
+            return Err(Error::InvalidEndpoint { endpoint: endpoint.clone(), source: *source });
+        }
+        #[cfg(feature="test-record")]
+        if let Some(cassette) = &self.cassette {
+            let (status, body) = match cassette.mode() {
+                cassette::Mode::Record => {
+                    let response = self.client.get(&url).send().await?;
+                    let status = response.status().as_u16();
+                    let body = response.bytes().await?;
+                    cassette.save(&url, status, &body)?;
+                    (status, body)
+                },
+                cassette::Mode::Replay => cassette.load(&url)?,
+            };
+            let response = http::Response::builder()
+                .status(status)
+                .body(body)
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+            return Self::check_status(path, reqwest::Response::from(response)).await;
+        }
+        Self::check_status(path, self.client.get(url).send().await?).await
+    }
+    /// Build the URL of `path` with the format `ext` (e.g. `json`).
+    ///
+    /// The path is joined to [base_url](#method.base_url), so a trailing slash of the endpoint makes no difference.
+    fn url(&self, path: &str, ext: &str) -> Result<String, Error> {
+        let url = self.base_url.join(&format!("{}.{}", path, ext)).map_err(|err| Error::InvalidEndpoint {
+            endpoint: self.base_url.to_string(),
+            source: err,
+        })?;
+        Ok(url.to_string())
+    }
+    /// Run `request` for `path`, retrying failures as configured by [Builder::retries](./struct.Builder.html#method.retries).
+    ///
+    /// Retries are delayed with exponential backoff (see [Builder::retry_backoff](./struct.Builder.html#method.retry_backoff)).
+    /// Each retry is logged at the `warn` level with the path, the attempt number and the error.
+    /// All the attempts have to complete by the deadline set by [with_deadline](#method.with_deadline).
+    async fn with_retry<T, F, Fut>(&self, path: &str, request: F) -> Result<T, Error>
+        where F: Fn() -> Fut, Fut: Future<Output = Result<T, Error>>
+    {
+        self.within_deadline(async {
+            let mut attempt = 0;
+            loop {
+                let permit = self.acquire().await;
+                let result = request().await;
+                drop(permit);
+                let err = match result {
+                    Ok(result) => return Ok(result),
+                    Err(err) => err,
+                };
+                attempt += 1;
+                let retry = attempt <= self.retries && match &self.retry_if {
+                    Some(predicate) => (predicate.0)(&err, attempt),
+                    None => default_retry_if(&err, attempt),
+                };
+                if !retry {
+                    return Err(err);
+                }
+                log::warn!("retrying {} attempt {} after {}", path, attempt + 1, err);
+                tokio::time::sleep(self.retry_backoff.saturating_mul(2u32.pow((attempt - 1).min(10)))).await;
+            }
+        }).await
+    }
+    /// Call the REST endpoint and parse it as a JSON.
+    ///
+    /// A malformed response is reported as `Error::Decode` with `path`.
+    pub async fn call_json<T: for<'de> Deserialize<'de>>(&self, path: &str) -> Result<T, Error> {
+        self.call_json_with_query(path, "").await
+    }
+    /// Same as [call_json](#method.call_json), but appends `query` (e.g. `"?verbose=false"`) to the URL.
+    async fn call_json_with_query<T: for<'de> Deserialize<'de>>(&self, path: &str, query: &str) -> Result<T, Error> {
+        self.with_retry(path, || async move {
+            let url = self.url(path, "json")? + query;
+            let body = self.get(path, url).await?
+                .text().await?;
+            Self::parse_json(path, &body)
+        }).await
+    }
+    /// Parse the JSON response `body` for `path`.
+    fn parse_json<T: for<'de> Deserialize<'de>>(path: &str, body: &str) -> Result<T, Error> {
+        serde_json::from_str(body).map_err(|err| Error::decode(path, err))
+    }
+    /// Call the REST endpoint (binary).
+    ///
+    /// The buffer is preallocated from `Content-Length` (up to 32 MiB) if the node sends it,
+    /// so large blocks are read without reallocations.
+    pub async fn call_bin(&self, path: &str) -> Result<bytes::Bytes, Error> {
+        const MAX_PREALLOCATION: u64 = 32 * 1024 * 1024;
+        self.with_retry(path, || async move {
+            let url = self.url(path, "bin")?;
+            let mut response = self.get(path, url).await?;
+            let capacity = response.content_length().unwrap_or(0).min(MAX_PREALLOCATION);
+            let mut result = bytes::BytesMut::with_capacity(capacity as usize);
+            while let Some(chunk) = response.chunk().await? {
+                result.extend_from_slice(&chunk);
+            }
+            Ok(result.freeze())
+        }).await
+    }
+    /// Call the REST endpoint with the format `ext` (e.g. `json` or `hex`) and return the body as is.
+    pub async fn call_text(&self, path: &str, ext: &str) -> Result<String, Error> {
+        self.with_retry(path, || async move {
+            let url = self.url(path, ext)?;
+            let result = self.get(path, url).await?
+                .text().await?;
+            Ok(result)
+        }).await
+    }
+    /// Call the REST endpoint (binary) and write the body hex-encoded to `writer` as it arrives.
+    ///
+    /// Unlike [call_hex](#method.call_hex), the whole hex string is never held in memory.
+    /// Returns the number of bytes written. Failures are not retried since a part of the body may have been written.
+    pub async fn call_bin_hex_to<W: tokio::io::AsyncWrite + Unpin>(&self, path: &str, writer: &mut W) -> Result<u64, Error> {
+        use tokio::io::AsyncWriteExt;
+        self.within_deadline(async {
+            let _permit = self.acquire().await;
+            let url = self.url(path, "bin")?;
+            let mut response = self.get(path, url).await?;
+            let mut written = 0;
+            while let Some(chunk) = response.chunk().await? {
+                let hex = chunk.to_hex();
+                writer.write_all(hex.as_bytes()).await?;
+                written += hex.len() as u64;
+            }
+            writer.flush().await?;
+            Ok(written)
+        }).await
+    }
+    /// Call the REST endpoint (hex).
+    pub async fn call_hex(&self, path: &str) -> Result<String, Error> {
+        let mut result = self.call_text(path, "hex").await?;
+        // Trim last '\n'.
+        result.pop();
+        Ok(result)
+    }
+    /// Call the REST endpoint (hex) and decode the hex into bytes.
+    ///
+    /// Returns `Error::BitcoinHexError` if the response is not a valid hex string.
+    pub async fn call_hex_bytes(&self, path: &str) -> Result<bytes::Bytes, Error> {
+        let result = self.call_hex(path).await?;
+        Ok(Vec::<u8>::from_hex(result.trim_end())?.into())
+    }
+    /// Call the [/tx](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#transactions) endpoint.
+    pub async fn tx(&self, txhash: &Txid) -> Result<Transaction, Error> {
+        let path = ["tx", &txhash.to_string()].join("/");
+        let result = self.call_bin(&path).await?;
+        Transaction::consensus_decode(result.as_ref()).map_err(|err| Error::decode(&path, err))
+    }
+    /// Same as [tx](#method.tx), but strips the witness data from the transaction.
+    ///
+    /// The returned transaction serializes in the legacy (non-segwit) format.
+    /// Note that the txid never commits to the witness, so `txid()` is the same with or without witnesses,
+    /// while `wtxid()` of the stripped transaction equals its txid.
+    pub async fn tx_no_witness(&self, txhash: &Txid) -> Result<Transaction, Error> {
+        let mut tx = self.tx(txhash).await?;
+        for input in tx.input.iter_mut() {
+            input.witness.clear();
+        }
+        Ok(tx)
+    }
+    /// Fetch multiple transactions with up to `concurrency` requests in flight.
+    ///
+    /// If `deadline` is given and elapses before all the transactions are fetched,
+    /// the outstanding requests are cancelled and `Error::DeadlineExceeded` is returned.
+    /// Dropping the returned future cancels them as well (see [Cancellation](./index.html#cancellation)).
+    pub async fn txs(&self, txhashes: &[Txid], concurrency: usize, deadline: Option<Duration>) -> Result<Vec<Transaction>, Error> {
+        run_with_deadline(deadline, stream::iter(txhashes)
+            .map(|txhash| self.tx(txhash))
+            .buffered(concurrency.max(1))
+            .try_collect()
+        ).await
+    }
+    /// Call the [/block](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#blocks) endpoint.
+    pub async fn block(&self, blockhash: &BlockHash) -> Result<Block, Error> {
+        let path = ["block", &blockhash.to_string()].join("/");
+        let result = self.call_bin(&path).await?;
+        Block::consensus_decode(result.as_ref()).map_err(|err| Error::decode(&path, err))
+    }
+    /// Write the hex-encoded block to `writer` as it is downloaded, without building the whole hex string in memory.
+    ///
+    /// See [call_bin_hex_to](#method.call_bin_hex_to).
+    pub async fn block_hex_to<W: tokio::io::AsyncWrite + Unpin>(&self, blockhash: &BlockHash, writer: &mut W) -> Result<u64, Error> {
+        self.call_bin_hex_to(&["block", &blockhash.to_string()].join("/"), writer).await
+    }
+    /// Fetch multiple blocks with up to `concurrency` requests in flight.
+    ///
+    /// If `deadline` is given and elapses before all the blocks are fetched,
+    /// the outstanding requests are cancelled and `Error::DeadlineExceeded` is returned.
+    /// Dropping the returned future cancels them as well (see [Cancellation](./index.html#cancellation)).
+    pub async fn blocks(&self, blockhashes: &[BlockHash], concurrency: usize, deadline: Option<Duration>) -> Result<Vec<Block>, Error> {
+        run_with_deadline(deadline, stream::iter(blockhashes)
+            .map(|blockhash| self.block(blockhash))
+            .buffered(concurrency.max(1))
+            .try_collect()
+        ).await
+    }
+    /// Fetch the block and compute the outpoints it creates and spends, in block order.
+    ///
+    /// Returns `(created, spent)`. The coinbase input is not counted as spent,
+    /// and provably unspendable outputs (e.g. `OP_RETURN`) are not counted as created.
+    /// An output created and spent within the same block appears in both lists.
+    /// Note that Bitcoin Core never adds the genesis coinbase output to the UTXO set.
+    pub async fn block_utxo_delta(&self, blockhash: &BlockHash) -> Result<(Vec<OutPoint>, Vec<OutPoint>), Error> {
+        let block = self.block(blockhash).await?;
+        Ok(utxo_delta(&block))
+    }
+    /// Fetch the block and list every output with its outpoint, e.g. for building an address index.
+    ///
+    /// See [block_outputs](./fn.block_outputs.html).
+    pub async fn block_outputs(&self, blockhash: &BlockHash) -> Result<Vec<(OutPoint, TxOut)>, Error> {
+        let block = self.block(blockhash).await?;
+        Ok(block_outputs(&block))
+    }
+    /// Fetch the block and return its coinbase scriptSig bytes (the miner's message).
+    pub async fn coinbase_message(&self, blockhash: &BlockHash) -> Result<Vec<u8>, Error> {
+        let block = self.block(blockhash).await?;
+        Ok(coinbase_message(&block))
+    }
+    /// Fetch the block and return the height encoded in its coinbase (BIP34), or `None` for pre-BIP34 blocks.
+    ///
+    /// This can be checked against [blockhashbyheight](#method.blockhashbyheight).
+    /// See [coinbase_height](./fn.coinbase_height.html) for the caveats.
+    pub async fn coinbase_height(&self, blockhash: &BlockHash) -> Result<Option<u32>, Error> {
+        let block = self.block(blockhash).await?;
+        Ok(coinbase_height(&block))
+    }
+    /// Fetch the genesis block and return its coinbase scriptSig bytes, which identify the chain
+    /// (e.g. "The Times 03/Jan/2009 Chancellor on brink of second bailout for banks" on Bitcoin).
+    ///
+    /// The genesis hash is looked up by [genesis_hash](#method.genesis_hash).
+    pub async fn genesis_coinbase_message(&self) -> Result<Vec<u8>, Error> {
+        let genesis_hash = self.genesis_hash().await?;
+        self.coinbase_message(&genesis_hash).await
+    }
+    /// Same as [coinbase_message](#method.coinbase_message), but converts the message to a string,
+    /// replacing invalid UTF-8 sequences (e.g. the BIP34 height push) with `U+FFFD`.
+    pub async fn coinbase_message_lossy(&self, blockhash: &BlockHash) -> Result<String, Error> {
+        let message = self.coinbase_message(blockhash).await?;
+        Ok(String::from_utf8_lossy(&message).into_owned())
+    }
+    /// Fetch the block and sum up the output values of all its transactions (including the coinbase).
+    pub async fn block_output_value(&self, blockhash: &BlockHash) -> Result<Amount, Error> {
+        let block = self.block(blockhash).await?;
+        sum_output_values(block.txdata.iter().flat_map(|tx| tx.output.iter()))
+    }
+    /// Fetch the block and compute the miner revenue, i.e. the total value of the coinbase outputs.
+    ///
+    /// The coinbase may claim at most the block subsidy plus the fees of the block's transactions,
+    /// so the result equals `subsidy + fees` unless the miner under-claimed. No prevouts are needed.
+    /// A result above `subsidy + fees` would make the block invalid.
+    pub async fn miner_revenue(&self, blockhash: &BlockHash) -> Result<Amount, Error> {
+        let block = self.block(blockhash).await?;
+        sum_output_values(block.txdata.iter().take(1).flat_map(|tx| tx.output.iter()))
+    }
+    /// Compute the total fees of the block at `height` as its [miner revenue](#method.miner_revenue)
+    /// minus the [block subsidy](./fn.block_subsidy.html), without needing any prevouts.
+    /// The halving interval is taken from [subsidy_halving_interval](#method.subsidy_halving_interval).
+    ///
+    /// If the miner claimed less than the subsidy, the unclaimed part is lost and `Amount::ZERO` is returned.
+    /// The result is thus a lower bound of the fees paid by the transactions if the miner under-claimed.
+    pub async fn block_fees(&self, blockhash: &BlockHash, height: u32) -> Result<Amount, Error> {
+        let (revenue, interval) = futures::try_join!(self.miner_revenue(blockhash), self.subsidy_halving_interval())?;
+        let subsidy = block_subsidy(height, interval);
+        Ok(revenue.checked_sub(subsidy).unwrap_or(Amount::ZERO))
+    }
+    /// Fetch the block and check that it is the requested one, its transactions match the merkle root,
+    /// and its PoW is valid, reporting each result separately.
+    ///
+    /// This helps to pinpoint how a block delivered through a proxy is corrupted.
+    /// See [BlockIntegrity::check](./struct.BlockIntegrity.html#method.check).
+    pub async fn block_integrity(&self, blockhash: &BlockHash) -> Result<BlockIntegrity, Error> {
+        let block = self.block(blockhash).await?;
+        Ok(BlockIntegrity::check(&block, blockhash))
+    }
+    /// Fetch the block and extract the witness commitment from its coinbase.
+    ///
+    /// See [witness_commitment](./fn.witness_commitment.html).
+    pub async fn witness_commitment(&self, blockhash: &BlockHash) -> Result<Option<[u8; 32]>, Error> {
+        let block = self.block(blockhash).await?;
+        Ok(witness_commitment(&block))
+    }
+    /// Fetch the block and convert it into a [BIP152](https://github.com/bitcoin/bips/blob/master/bip-0152.mediawiki)
+    /// compact block, salting the short ids with `nonce`.
+    pub async fn compact_block(&self, blockhash: &BlockHash, nonce: u64) -> Result<HeaderAndShortIds, Error> {
+        let block = self.block(blockhash).await?;
+        Ok(HeaderAndShortIds::from_block(&block, nonce))
+    }
+    /// Call the [/block/notxdetails](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#blocks) endpoint.
+    pub async fn block_notxdetails(&self, blockhash: &BlockHash) -> Result<BlockHeader, Error> {
+        let path = ["block", "notxdetails", &blockhash.to_string()].join("/");
+        let result = self.call_bin(&path).await?;
+        BlockHeader::consensus_decode(result.as_ref()).map_err(|err| Error::decode(&path, err))
+    }
+    /// Same as [tx](#method.tx), but takes the raw txid bytes.
+    ///
+    /// The bytes are in the internal (consensus-serialized) byte order,
+    /// i.e. reversed compared to the hex string representation.
+    pub async fn tx_by_bytes(&self, txhash: &[u8; 32]) -> Result<Transaction, Error> {
+        self.tx(&Txid::from_inner(*txhash)).await
+    }
+    /// Same as [block](#method.block), but takes the raw block hash bytes.
+    ///
+    /// The bytes are in the internal (consensus-serialized) byte order,
+    /// i.e. reversed compared to the hex string representation.
+    pub async fn block_by_bytes(&self, blockhash: &[u8; 32]) -> Result<Block, Error> {
+        self.block(&BlockHash::from_inner(*blockhash)).await
+    }
+    /// Same as [block_notxdetails](#method.block_notxdetails), but takes the raw block hash bytes.
+    ///
+    /// The bytes are in the internal (consensus-serialized) byte order,
+    /// i.e. reversed compared to the hex string representation.
+    pub async fn block_notxdetails_by_bytes(&self, blockhash: &[u8; 32]) -> Result<BlockHeader, Error> {
+        self.block_notxdetails(&BlockHash::from_inner(*blockhash)).await
+    }
+    /// Call the [/headers](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#blockheaders) endpoint.
+    ///
+    /// `count` must be within `1..=MAX_HEADERS_RESULTS`; Bitcoin Core rejects other counts with HTTP 400
+    /// (returned as `Error::Http`). Fewer headers are returned if the chain ends earlier.
+    pub async fn headers(&self, count: u32, blockhash: &BlockHash) -> Result<Vec<BlockHeader>, Error> {
+        self.headers_iter(count, blockhash).await?.collect()
+    }
+    /// Fetch up to `count` headers starting at `blockhash` in `direction`, in the order they are walked.
+    ///
+    /// `Direction::Forward` is the same as [headers](#method.headers).
+    /// The REST interface only serves headers forward, so `Direction::Backward` follows `prev_blockhash`
+    /// with one [block_notxdetails](#method.block_notxdetails) request per header,
+    /// returning `blockhash`'s header first and stopping early at genesis.
+    pub async fn headers_directional(&self, count: u32, blockhash: &BlockHash, direction: Direction) -> Result<Vec<BlockHeader>, Error> {
+        if direction == Direction::Forward {
+            return self.headers(count, blockhash).await;
+        }
+        // `count` may be as large as `u32::MAX` to walk back to genesis.
+        let mut headers = Vec::with_capacity(count.min(MAX_HEADERS_RESULTS) as usize);
+        let mut next = *blockhash;
+        while headers.len() < count as usize {
+            let header = self.block_notxdetails(&next).await?;
+            next = header.prev_blockhash;
+            headers.push(header);
+            if next == BlockHash::from_inner([0u8; 32]) {
+                break;
+            }
+        }
+        Ok(headers)
+    }
+    /// Same as [headers](#method.headers), but verifies that the headers start at `blockhash`,
+    /// the first header builds on `expected_first_prev` and each following header links to the previous one.
+    ///
+    /// This guards header sync through an untrusted proxy against a tampered or reordered header chain.
+    /// Returns `Error::HeaderChainMismatch` on failure.
+    pub async fn headers_verified(&self, count: u32, blockhash: &BlockHash, expected_first_prev: &BlockHash) -> Result<Vec<BlockHeader>, Error> {
+        let headers = self.headers(count, blockhash).await?;
+        if let Some(first) = headers.first() {
+            if first.block_hash() != *blockhash {
+                return Err(Error::HeaderChainMismatch { index: 0 });
+            }
+        }
+        verify_header_chain(&headers, expected_first_prev)?;
+        Ok(headers)
+    }
+    /// Fetch all the headers from genesis to the tip (as of the start of the call), ordered by height.
+    ///
+    /// Headers are fetched in batches of [MAX_HEADERS_RESULTS](./constant.MAX_HEADERS_RESULTS.html),
+    /// each batch starting from the last header of the previous one.
+    /// Takes about `height / MAX_HEADERS_RESULTS` sequential requests.
+    pub async fn all_headers(&self) -> Result<Vec<BlockHeader>, Error> {
+        let tip_height = self.chaininfo().await?.blocks as usize;
+        let mut start = self.blockhashbyheight(0).await?;
+        let mut ret: Vec<BlockHeader> = Vec::with_capacity(tip_height + 1);
+        loop {
+            let headers = self.headers(MAX_HEADERS_RESULTS, &start).await?;
+            // Each batch after the first starts with the last header of the previous one.
+            let skip = if ret.is_empty() { 0 } else { 1 };
+            let len = ret.len();
+            ret.extend(headers.into_iter().skip(skip));
+            if ret.len() == len || ret.len() > tip_height {
+                break;
+            }
+            start = ret[ret.len() - 1].block_hash();
+        }
+        // The last batch may run past the tip height if blocks were mined in the meantime.
+        ret.truncate(tip_height + 1);
+        Ok(ret)
+    }
+    /// Same as [headers](#method.headers), but decodes the headers lazily one at a time.
+    ///
+    /// The response body is buffered, while the headers are decoded only as the iterator advances.
+    pub async fn headers_iter(&self, count: u32, blockhash: &BlockHash) -> Result<HeadersIter, Error> {
+        let path = ["headers", &count.to_string(), &blockhash.to_string()].join("/");
+        let result = self.call_bin(&path).await?;
+        Ok(HeadersIter {
+            path,
+            bytes: result,
+            offset: 0,
+        })
+    }
+    /// Call the [/blockhashbyheight](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#blockhash-by-height) endpoint.
+    pub async fn blockhashbyheight(&self, height: u32) -> Result<BlockHash, Error> {
+        let path = ["blockhashbyheight", &height.to_string()].join("/");
+        let result = self.call_bin(&path).await?;
+        BlockHash::consensus_decode(result.as_ref()).map_err(|err| Error::decode(&path, err))
+    }
+    /// Call the [/chaininfo](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#chaininfo) endpoint.
+    pub async fn chaininfo(&self) -> Result<ChainInfo, Error> {
+        let result: ChainInfo = self.call_json("chaininfo").await?;
+        Ok(result)
+    }
+    /// Same as [chaininfo](#method.chaininfo), but returns the JSON string exactly as the node returned it.
+    pub async fn chaininfo_raw(&self) -> Result<String, Error> {
+        self.call_text("chaininfo", "json").await
+    }
+    /// Call the [/deploymentinfo](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#deployment-info) endpoint
+    /// for the chain tip. Available on Bitcoin Core >= 25.0.
+    pub async fn deploymentinfo(&self) -> Result<DeploymentInfo, Error> {
+        let result: DeploymentInfo = self.call_json("deploymentinfo").await?;
+        Ok(result)
+    }
+    /// Check whether the deployment (softfork) `name` is active at the chain tip.
+    ///
+    /// Bitcoin Core 23.0 and 24.x report neither, so the deployment is not found there.
+    async fn deployment_active(&self, name: &str) -> Result<bool, Error> {
+        let deployments = match self.chaininfo().await?.softforks {
+            Some(softforks) => softforks,
+            None => match self.deploymentinfo().await {
+                Ok(deploymentinfo) => deploymentinfo.deployments,
+                Err(Error::Http { status: reqwest::StatusCode::NOT_FOUND, .. }) => {
+                    return Err(Error::DeploymentNotFound(name.to_string()));
+                },
+                Err(err) => return Err(err),
+            },
+        };
+        deployments.get(name)
+            .map(|deployment| deployment.active)
+            .ok_or_else(|| Error::DeploymentNotFound(name.to_string()))
+    }
+    /// Check whether segwit (BIP141/143/147) is active at the chain tip.
+    ///
+    /// The `softforks` of [chaininfo](#method.chaininfo) is used if reported (Bitcoin Core < 23.0),
+    /// otherwise [deploymentinfo](#method.deploymentinfo) is called (Bitcoin Core >= 25.0).
+    /// Bitcoin Core 23.0 and 24.x are not supported since they report neither;
+    /// `Error::DeploymentNotFound` is returned there, as well as if the node does not know the deployment.
+    pub async fn segwit_active(&self) -> Result<bool, Error> {
+        self.deployment_active("segwit").await
+    }
+    /// Check whether taproot (BIP340/341/342) is active at the chain tip.
+    ///
+    /// The `softforks` of [chaininfo](#method.chaininfo) is used if reported (Bitcoin Core < 23.0),
+    /// otherwise [deploymentinfo](#method.deploymentinfo) is called (Bitcoin Core >= 25.0).
+    /// Bitcoin Core 23.0 and 24.x are not supported since they report neither;
+    /// `Error::DeploymentNotFound` is returned there, as well as if the node does not know the deployment.
+    pub async fn taproot_active(&self) -> Result<bool, Error> {
+        self.deployment_active("taproot").await
+    }
+    fn getutxos_path(checkmempool: bool, txids: &[Txid]) -> String {
+        let outpoints: Vec<OutPoint> = txids.iter().enumerate()
+            .map(|(i, txid)| OutPoint::new(*txid, i as u32))
+            .collect();
+        Self::getutxos_outpoints_path(checkmempool, &outpoints)
+    }
+    fn getutxos_outpoints_path(checkmempool: bool, outpoints: &[OutPoint]) -> String {
+        let mut path = Vec::with_capacity(1 + if checkmempool { 1 } else { 0 } + outpoints.len());
+        path.push("getutxos".to_string());
+        if checkmempool {
+            path.push("checkmempool".to_string());
+        }
+        for outpoint in outpoints.iter() {
+            path.push([outpoint.txid.to_string(), outpoint.vout.to_string()].join("-"));
+        }
+        path.join("/")
+    }
+    /// Call the [/getutxos](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#query-utxo-set) endpoint.
+    ///
+    /// Bitcoin Core accepts up to [MAX_GETUTXOS_OUTPOINTS](./constant.MAX_GETUTXOS_OUTPOINTS.html) outpoints per request
+    /// and `Error::TooManyOutpoints` is returned if `txids` is longer.
+    pub async fn getutxos(&self, checkmempool: bool, txids: &[Txid]) -> Result<UtxoData, Error> {
+        let result: UtxoData = self.call_json(&Self::getutxos_path(checkmempool, txids)).await?;
+        Ok(result)
+    }
+    /// Same as [getutxos](#method.getutxos), but uses the binary format.
+    ///
+    /// The binary format encodes values in satoshis, which are converted to the same `Amount`s as the JSON format.
+    /// Only `hex` of each `script_pub_key` is available in this format.
+    pub async fn getutxos_bin(&self, checkmempool: bool, txids: &[Txid]) -> Result<UtxoData, Error> {
+        let path = Self::getutxos_path(checkmempool, txids);
+        let result = self.call_bin(&path).await?;
+        UtxoData::consensus_decode(result.as_ref(), txids.len()).map_err(|err| Error::decode(&path, err))
+    }
+    /// Fetch the transaction of `outpoint` and return the output at its index.
+    ///
+    /// Returns `None` if the index is out of range. The output is returned regardless of whether it is spent;
+    /// use [unspent_txout](#method.unspent_txout) to take spentness into account.
+    pub async fn txout(&self, outpoint: &OutPoint) -> Result<Option<TxOut>, Error> {
+        let tx = self.tx(&outpoint.txid).await?;
+        Ok(tx.output.get(outpoint.vout as usize).cloned())
+    }
+    /// Look up `outpoint` in the UTXO set (including the mempool) via the
+    /// [/getutxos](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#query-utxo-set) endpoint.
+    ///
+    /// Returns `None` if the output is spent or does not exist.
+    pub async fn unspent_txout(&self, outpoint: &OutPoint) -> Result<Option<Utxo>, Error> {
+        let path = Self::getutxos_outpoints_path(true, &[*outpoint]);
+        let result: UtxoData = self.call_json(&path).await?;
+        Ok(result.utxos.into_iter().next())
+    }
+    /// Poll every `poll_interval` until the transaction `txid` has at least `confirmations` confirmations
+    /// and return the hash of the block containing it.
+    ///
+    /// Each poll calls the [/tx](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#transactions) endpoint,
+    /// which serves mempool transactions and, with `-txindex`, confirmed ones along with their block hash.
+    /// The confirmations are then counted from the block's header, so a block reorged out of the chain does not count.
+    /// If the node does not know the transaction (e.g. it is confirmed but the node lacks `-txindex`),
+    /// the first [MAX_GETUTXOS_OUTPOINTS](./constant.MAX_GETUTXOS_OUTPOINTS.html) outputs are looked up via the
+    /// [/getutxos](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#query-utxo-set) endpoint instead;
+    /// only this fallback misses a transaction whose probed outputs are all spent or provably unspendable.
+    ///
+    /// Returns `Error::ConfirmationTimeout` if the transaction is not confirmed within `timeout`,
+    /// which tells whether it was in the mempool at the last poll. This also covers a transaction which was never
+    /// broadcast or was dropped from the mempool. The deadline set by [with_deadline](#method.with_deadline) applies as well.
+    pub async fn wait_for_confirmation(&self, txid: &Txid, confirmations: u32, poll_interval: Duration, timeout: Duration) -> Result<BlockHash, Error> {
+        let timeout_at = tokio::time::Instant::now() + timeout;
+        let path = ["tx", &txid.to_string()].join("/");
+        loop {
+            let in_mempool = match self.call_json::<TxBlockHash>(&path).await {
+                Ok(TxBlockHash { blockhash: Some(blockhash) }) => {
+                    let blockhash = blockhash.parse::<BlockHash>()?;
+                    if self.block_confirmations(&blockhash).await? >= confirmations as i64 {
+                        return Ok(blockhash);
+                    }
+                    false
+                },
+                Ok(TxBlockHash { blockhash: None }) => true,
+                Err(Error::TxNotFound { .. }) => match self.probe_utxo_height(txid).await? {
+                    UtxoProbe::Confirmed { height, chain_height } => {
+                        if (chain_height + 1).saturating_sub(height) >= confirmations {
+                            return self.blockhashbyheight(height).await;
+                        }
+                        false
+                    },
+                    UtxoProbe::InMempool => true,
+                    UtxoProbe::NotFound => false,
+                },
+                Err(err) => return Err(err),
+            };
+            let now = tokio::time::Instant::now();
+            if now >= timeout_at {
+                return Err(Error::ConfirmationTimeout { txid: *txid, in_mempool });
+            }
+            self.within_deadline(async {
+                tokio::time::sleep(poll_interval.min(timeout_at - now)).await;
+                Ok(())
+            }).await?;
+        }
+    }
+    /// Get the number of confirmations of the block `blockhash` from its JSON header.
+    ///
+    /// The count is `-1` if the block is not in the active chain.
+    async fn block_confirmations(&self, blockhash: &BlockHash) -> Result<i64, Error> {
+        let path = ["headers", "1", &blockhash.to_string()].join("/");
+        let headers: Vec<HeaderConfirmations> = self.call_json(&path).await?;
+        Ok(headers.first().map_or(-1, |header| header.confirmations))
+    }
+    /// Look up the first [MAX_GETUTXOS_OUTPOINTS](./constant.MAX_GETUTXOS_OUTPOINTS.html) outputs of `txid`
+    /// via getutxos (including the mempool) and report where the first unspent one is.
+    async fn probe_utxo_height(&self, txid: &Txid) -> Result<UtxoProbe, Error> {
+        // The height getutxos reports for mempool outputs.
+        const MEMPOOL_HEIGHT: u32 = 0x7fffffff;
+        let outpoints: Vec<OutPoint> = (0..MAX_GETUTXOS_OUTPOINTS as u32)
+            .map(|vout| OutPoint::new(*txid, vout))
+            .collect();
+        let result: UtxoData = self.call_json(&Self::getutxos_outpoints_path(true, &outpoints)).await?;
+        Ok(match result.utxos.first() {
+            Some(utxo) if utxo.height == MEMPOOL_HEIGHT => UtxoProbe::InMempool,
+            Some(utxo) => UtxoProbe::Confirmed { height: utxo.height, chain_height: result.chain_height },
+            None => UtxoProbe::NotFound,
+        })
+    }
+    /// Get the chain tip height and block hash.
+    ///
+    /// This calls the [/chaininfo](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#chaininfo) endpoint.
+    /// If the tip height is already known, [tip_hash_by_height](#method.tip_hash_by_height) is cheaper.
+    pub async fn tip(&self) -> Result<(u32, BlockHash), Error> {
+        let chaininfo = self.chaininfo().await?;
+        Ok((chaininfo.blocks, chaininfo.bestblockhash.parse::<BlockHash>()?))
+    }
+    /// Get the header of the chain tip.
+    ///
+    /// This takes two requests: [chaininfo](#method.chaininfo) for the tip hash
+    /// and [block_notxdetails](#method.block_notxdetails) for the header.
+    pub async fn best_block_header(&self) -> Result<BlockHeader, Error> {
+        let (_, blockhash) = self.tip().await?;
+        self.block_notxdetails(&blockhash).await
+    }
+    /// Get the tip block hash when the tip `height` is already known.
+    ///
+    /// This is a thin wrapper of [blockhashbyheight](#method.blockhashbyheight),
+    /// which is much lighter than [chaininfo](#method.chaininfo).
+    /// It returns the tip hash only if `height` actually is the tip height;
+    /// if the chain has grown since, the hash of an ancestor of the tip is returned.
+    pub async fn tip_hash_by_height(&self, height: u32) -> Result<BlockHash, Error> {
+        self.blockhashbyheight(height).await
+    }
+    /// Get the number of blocks between block subsidy halvings.
+    ///
+    /// Returns the interval given by [Builder::subsidy_halving_interval](./struct.Builder.html#method.subsidy_halving_interval) if any.
+    /// Otherwise, derives it from the network of [params](#method.params)
+    /// (see [subsidy_halving_interval](./fn.subsidy_halving_interval.html)).
+    pub async fn subsidy_halving_interval(&self) -> Result<u32, Error> {
+        if let Some(interval) = self.subsidy_halving_interval {
+            return Ok(interval);
+        }
+        Ok(subsidy_halving_interval(self.params().await?.network))
+    }
+    /// Get the consensus parameters of the node's chain.
+    ///
+    /// Returns the parameters given by [Builder::params](./struct.Builder.html#method.params) if any.
+    /// Otherwise, calls the [/chaininfo](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#chaininfo) endpoint
+    /// and returns rust-bitcoin's parameters for the reported chain.
+    /// Note that altcoin forks reporting Bitcoin's chain names get Bitcoin's parameters.
+    pub async fn params(&self) -> Result<Params, Error> {
+        if let Some(params) = &self.params {
+            return Ok(params.clone());
+        }
+        Ok(Params::new(self.network().await?))
+    }
+    /// Return the network the node is on.
+    ///
+    /// Calls the [/chaininfo](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#chaininfo) endpoint,
+    /// but only the `chain` field is parsed.
+    /// Returns `Error::UnknownChain` if the chain name is not one of Bitcoin Core's.
+    ///
+    /// The result is cached after the first successful call.
+    pub async fn network(&self) -> Result<Network, Error> {
+        let network = self.network.get_or_try_init(|| async {
+            let result: ChainName = self.call_json("chaininfo").await?;
+            network_from_chain(&result.chain)
+        }).await?;
+        Ok(*network)
+    }
+    /// Return the hash of the genesis block.
+    ///
+    /// The result is cached after the first successful call.
+    pub async fn genesis_hash(&self) -> Result<BlockHash, Error> {
+        let genesis_hash = self.genesis_hash.get_or_try_init(|| self.blockhashbyheight(0)).await?;
+        Ok(*genesis_hash)
+    }
+    /// Call the [/mempool/info](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#memory-pool) endpoint.
+    pub async fn mempool_info(&self) -> Result<MempoolInfo, Error> {
+        let result: MempoolInfo = self.call_json("mempool/info").await?;
+        Ok(result)
+    }
+    /// Call the [/mempool/contents](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#memory-pool) endpoint.
+    ///
+    /// Returns the verbose mempool entries keyed by txid.
+    pub async fn mempool_contents(&self) -> Result<HashMap<String, MempoolEntry>, Error> {
+        let result: HashMap<String, MempoolEntry> = self.call_json("mempool/contents").await?;
+        Ok(result)
+    }
+    /// Check whether the transaction `txid` is currently in the mempool.
+    ///
+    /// This calls the [/mempool/contents](https://github.com/bitcoin/bitcoin/blob/master/doc/REST-interface.md#memory-pool)
+    /// endpoint with `verbose=false` and looks up `txid`, so each call downloads the txids of the whole mempool.
+    /// Bitcoin Core < 25.0 ignores `verbose=false` and returns the full entries, which are then downloaded instead.
+    /// Avoid calling it in a tight loop on a node with a large mempool.
+    pub async fn is_in_mempool(&self, txid: &Txid) -> Result<bool, Error> {
+        let txids: MempoolTxids = self.call_json_with_query("mempool/contents", "?verbose=false").await?;
+        Ok(txids.contains(&txid.to_string()))
+    }
+    /// Fetch the block at `height` and decode it as `T`.
+    ///
+    /// The whole response must be consumed by the decoder, otherwise an error is returned.
+    /// E.g. `T = Block` works, while `T = BlockHeader` fails because of the trailing transactions.
+    pub async fn decode_at_height<T: Decodable>(&self, height: u32) -> Result<T, Error> {
+        let blockhash = self.blockhashbyheight(height).await?;
+        let path = ["block", &blockhash.to_string()].join("/");
+        let result = self.call_bin(&path).await?;
+        bitcoin::consensus::deserialize(result.as_ref()).map_err(|err| Error::decode(&path, err))
+    }
+    /// Fetch the block at `height`.
+    async fn block_at_height(&self, height: u32) -> Result<Block, Error> {
+        let blockhash = self.blockhashbyheight(height).await?;
+        self.block(&blockhash).await
+    }
+    /// Fetch the blocks at heights `start..end` with up to `concurrency` requests in flight
+    /// and send them with their heights to `tx`.
+    ///
+    /// Blocks are sent in the order they arrive, which is not necessarily the height order.
+    /// A failed height does not stop the export; all the failures are reported in the returned summary.
+    /// The export stops early if the receiver is dropped.
+    ///
+    /// Dropping the returned future stops the export and cancels the requests in flight
+    /// (see [Cancellation](./index.html#cancellation)); the blocks already sent stay in the channel.
+    pub async fn export_blocks(&self, start: u32, end: u32, tx: tokio::sync::mpsc::Sender<(u32, Block)>, concurrency: usize) -> ExportSummary {
+        let mut results = stream::iter(start..end)
+            .map(|height| async move { (height, self.block_at_height(height).await) })
+            .buffer_unordered(concurrency.max(1));
+        let mut summary = ExportSummary::default();
+        while let Some((height, result)) = results.next().await {
+            match result {
+                Ok(block) => {
+                    if tx.send((height, block)).await.is_err() {
+                        summary.receiver_closed = true;
+                        break;
+                    }
+                    summary.exported += 1;
+                },
+                Err(err) => summary.failed.push((height, err)),
+            }
+        }
+        summary
+    }
+    /// Stream the blocks at heights `start..end` in height order, prefetching up to `lookahead` blocks concurrently.
+    ///
+    /// Unlike [export_blocks](#method.export_blocks), a block is yielded only after all the lower ones,
+    /// so a slow block holds back the later ones (while they keep being prefetched).
+    /// A failed height is yielded as an error and the stream goes on.
+    /// Dropping the stream cancels the prefetching requests (see [Cancellation](./index.html#cancellation)).
+    pub fn blocks_in_order(&self, start: u32, end: u32, lookahead: usize) -> impl futures::Stream<Item = Result<(u32, Block), Error>> + '_ {
+        stream::iter(start..end)
+            .map(move |height| async move { self.block_at_height(height).await.map(|block| (height, block)) })
+            .buffered(lookahead.max(1))
+    }
+    /// Fetch the blocks at `heights` with up to `concurrency` requests in flight,
+    /// returning one result per height in the same order.
+    ///
+    /// Unlike [blocks](#method.blocks), a failed height does not abort the batch;
+    /// its error is returned in place of the block. The heights need not be contiguous or sorted.
+    /// Dropping the returned future cancels the outstanding requests
+    /// (see [Cancellation](./index.html#cancellation)).
+    pub async fn blocks_at_heights(&self, heights: &[u32], concurrency: usize) -> Vec<Result<Block, Error>> {
+        stream::iter(heights)
+            .map(|&height| self.block_at_height(height))
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+    /// Fetch the header of the block at `height`.
+    async fn header_at_height(&self, height: u32) -> Result<BlockHeader, Error> {
+        let blockhash = self.blockhashbyheight(height).await?;
+        self.block_notxdetails(&blockhash).await
+    }
+    /// Fetch the header at `height` and return the target decoded from its `bits`.
+    ///
+    /// rust-bitcoin 0.26 has no dedicated `Target` type, so the target is returned as a `Uint256`
+    /// (the same value as `BlockHeader::target()`).
+    pub async fn target_at_height(&self, height: u32) -> Result<bitcoin::util::uint::Uint256, Error> {
+        let header = self.header_at_height(height).await?;
+        Ok(header.target())
+    }
+    /// Estimate the next difficulty adjustment from the headers of the current retarget window.
+    ///
+    /// Two headers are fetched: the first block of the current window and the chain tip.
+    /// The retarget interval and target spacing are taken from [params](#method.params).
+    pub async fn difficulty_adjustment(&self) -> Result<DifficultyAdjustment, Error> {
+        let (chaininfo, params) = futures::try_join!(self.chaininfo(), self.params())?;
+        let tip_height = chaininfo.blocks;
+        let interval = retarget_interval(&params).ok_or(Error::InvalidParams)?;
+        let window_start_height = tip_height - tip_height % interval;
+        let (window_start, tip) = futures::try_join!(
+            self.header_at_height(window_start_height),
+            self.header_at_height(tip_height),
+        )?;
+        DifficultyAdjustment::new(&params, window_start_height, &window_start, tip_height, &tip).ok_or(Error::InvalidParams)
+    }
+    /// Fetch the headers at the start and the end of the retarget epoch containing `height`.
+    ///
+    /// The epoch length is the difficulty adjustment interval of [params](#method.params),
+    /// so use [Builder::params](./struct.Builder.html#method.params) for altcoins with different retargeting.
+    /// The timestamps of the two headers give the actual timespan used for the next retarget.
+    /// The epoch has to be complete; otherwise the end header does not exist and an error is returned.
+    /// Returns `Error::InvalidParams` if the params give a zero interval,
+    /// and `Error::EpochOutOfRange` if the epoch would end past `u32::MAX`.
+    pub async fn retarget_boundaries(&self, height: u32) -> Result<(BlockHeader, BlockHeader), Error> {
+        let interval = retarget_interval(&self.params().await?).ok_or(Error::InvalidParams)?;
+        let start_height = height - height % interval;
+        let end_height = start_height.checked_add(interval - 1).ok_or(Error::EpochOutOfRange { height })?;
+        let (start, end) = futures::try_join!(
+            self.header_at_height(start_height),
+            self.header_at_height(end_height),
+        )?;
+        Ok((start, end))
+    }
+    /// Find the first block mined on or after `timestamp` (UNIX time).
+    ///
+    /// The heights between genesis and the tip are binary-searched by header timestamps,
+    /// which takes `O(log(height))` round trips.
+    /// Returns `None` if the tip is older than `timestamp`.
+    ///
+    /// Note that block timestamps are not strictly monotonic: a block only has to be newer than
+    /// the median time of the previous 11 blocks (MTP rule).
+    /// The search treats timestamps as if they were sorted, so the result is a height whose block
+    /// time is `>= timestamp` while the previous block time is `< timestamp`.
+    /// A block with an out-of-order timestamp may exist at a lower height.
+    pub async fn block_at_or_after_time(&self, timestamp: u32) -> Result<Option<(u32, BlockHash)>, Error> {
+        let tip_height = self.chaininfo().await?.blocks;
+        let tip_hash = self.blockhashbyheight(tip_height).await?;
+        if self.block_notxdetails(&tip_hash).await?.time < timestamp {
+            return Ok(None);
+        }
+        // The block at `high` is always on or after `timestamp`.
+        let mut low = 0;
+        let mut high = tip_height;
+        let mut high_hash = tip_hash;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let hash = self.blockhashbyheight(mid).await?;
+            if self.block_notxdetails(&hash).await?.time >= timestamp {
+                high = mid;
+                high_hash = hash;
+            } else {
+                low = mid + 1;
+            }
+        }
+        Ok(Some((high, high_hash)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use super::*;
+    #[tokio::test]
+    async fn reqwest_fail() {
+        let rest = try_new("http://invalid-url").unwrap();
+        assert!(rest.blockhashbyheight(0).await.is_err());
+    }
+    #[tokio::test]
+    #[allow(deprecated)]
+    async fn new_invalid_endpoint() {
+        let rest = new("not a url");
+        assert!(matches!(rest.chaininfo().await, Err(Error::InvalidEndpoint { .. })));
+    }
+    #[tokio::test]
+    async fn connect_fail() {
+        assert!(super::connect("http://invalid-url").await.is_err());
+        assert!(matches!(super::connect("localhost:8332").await, Err(Error::InvalidEndpoint { .. })));
+    }
+    #[tokio::test]
+    async fn check_status() {
+        let response = |status: u16, body: &str| reqwest::Response::from(
+            http::Response::builder().status(status).body(body.to_string()).unwrap()
+        );
+        assert!(Context::check_status("tx/00", response(200, "")).await.is_ok());
+        // The body Bitcoin Core's rest_tx sends for a missing transaction.
+        let txid = "0e3e2357e806b6cdb1f70b54c3a3a17b6714ee1f0e68bebb44a74b1efd512098";
+        let result = Context::check_status(&format!("tx/{}", txid), response(404, &format!("{} not found\r\n", txid))).await;
+        assert!(matches!(result, Err(Error::TxNotFound { .. })));
+        assert!(format!("{}", result.unwrap_err()).contains("-txindex"));
+        let result = Context::check_status("tx/00", response(400, "Invalid hash: 00\r\n")).await;
+        assert!(matches!(result, Err(Error::Http { status: reqwest::StatusCode::BAD_REQUEST, .. })));
+        assert_eq!(format!("{}", result.unwrap_err()), "HTTP error 400 Bad Request for tx/00: Invalid hash: 00");
+        let result = Context::check_status("getutxos/00-0", response(400, "Error: max outpoints exceeded (max: 15, tried: 16)\r\n")).await;
+        assert!(matches!(result, Err(Error::TooManyOutpoints { max: 15 })));
+        let result = Context::check_status("chaininfo", response(404, "")).await;
+        assert!(matches!(result, Err(Error::RestDisabled)));
+        // An unknown endpoint (e.g. deploymentinfo on Bitcoin Core < 25.0) is not mistaken for a disabled REST interface.
+        let result = Context::check_status("deploymentinfo", response(404, "")).await;
+        assert!(matches!(result, Err(Error::Http { status: reqwest::StatusCode::NOT_FOUND, .. })));
+        let result = Context::check_status("block/00", response(404, "00 not found\r\n")).await;
+        assert!(matches!(result, Err(Error::Http { .. })));
+    }
+    #[test]
+    fn parse_json_fail() {
+        let chaininfo: Result<ChainInfo, _> = Context::parse_json("chaininfo", r#"{"chain": "main", "blocks": "#);
+        // A malformed response will not get better by retrying.
+        assert!(!default_retry_if(chaininfo.as_ref().unwrap_err(), 1));
+        match chaininfo {
+            Err(Error::Decode { path, source }) => {
+                assert_eq!(path, "chaininfo");
+                assert!(source.is::<serde_json::Error>());
+            },
+            result => panic!("unexpected result: {:?}", result),
+        }
+        let result: Result<HashMap<String, MempoolEntry>, _> = Context::parse_json("mempool/contents", "<html>");
+        assert!(format!("{}", result.unwrap_err()).starts_with("failed to decode the response for mempool/contents: "));
+    }
+    #[tokio::test]
+    async fn builder_params() {
+        let mut params = Params::new(Network::Regtest);
+        params.pow_target_spacing = 90;
+        let rest = builder("http://invalid-url").params(params).build().unwrap();
+        assert_eq!(rest.params().await.unwrap().pow_target_spacing, 90);
+    }
+    #[test]
+    fn chain_to_network() {
+        assert_eq!(network_from_chain("main").unwrap(), Network::Bitcoin);
+        assert_eq!(network_from_chain("test").unwrap(), Network::Testnet);
+        assert_eq!(network_from_chain("signet").unwrap(), Network::Signet);
+        assert_eq!(network_from_chain("regtest").unwrap(), Network::Regtest);
+        assert!(matches!(network_from_chain("foo"), Err(Error::UnknownChain(_))));
+    }
+    #[tokio::test]
+    async fn retry_predicate() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        let counter = AtomicU32::new(0);
+        let attempts = &counter;
+        let failing_request = || async move {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(Error::AmountOverflow)
+        };
+        // No retries by default.
+        let rest = try_new("http://invalid-url").unwrap();
+        assert!(rest.with_retry("tx/00", failing_request).await.is_err());
+        assert_eq!(attempts.swap(0, Ordering::SeqCst), 1);
+        // The default predicate does not retry non-transport errors.
+        let rest = builder("http://invalid-url").retries(3).build().unwrap();
+        assert!(rest.with_retry("tx/00", failing_request).await.is_err());
+        assert_eq!(attempts.swap(0, Ordering::SeqCst), 1);
+        // The predicate sees the attempt numbers.
+        let rest = builder("http://invalid-url")
+            .retries(3)
+            .retry_if(|err, attempt| matches!(err, Error::AmountOverflow) && attempt < 3)
+            .build().unwrap();
+        assert!(rest.with_retry("tx/00", failing_request).await.is_err());
+        assert_eq!(attempts.swap(0, Ordering::SeqCst), 3);
+        // Connection failures are retried by default.
+        let rest = builder("http://invalid-url").retries(2).build().unwrap();
+        assert!(matches!(rest.blockhashbyheight(0).await, Err(Error::Connect(_))));
+    }
+    #[tokio::test]
+    async fn builder_fail() {
+        let rest = builder("http://invalid-url")
+            .tcp_nodelay(true)
+            .pool_idle_timeout(Some(Duration::from_secs(30)))
+            .pool_max_idle_per_host(4)
+            .resolve("invalid-url", ([127, 0, 0, 1], 0).into())
+            .build().unwrap();
+        assert!(rest.blockhashbyheight(0).await.is_err());
+    }
+    #[tokio::test]
+    async fn retry_backoff() {
+        let rest = builder("http://invalid-url")
+            .retries(2)
+            .retry_if(|_, _| true)
+            .retry_backoff(Duration::from_millis(50))
+            .build().unwrap();
+        let failing_request = || async { Err::<(), _>(Error::AmountOverflow) };
+        let start = std::time::Instant::now();
+        assert!(matches!(rest.with_retry("tx/00", failing_request).await, Err(Error::AmountOverflow)));
+        // 50ms before the first retry and 100ms before the second.
+        assert!(start.elapsed() >= Duration::from_millis(150));
+        let rest = rest.with_deadline(std::time::Instant::now() + Duration::from_millis(80));
+        assert!(matches!(rest.with_retry("tx/00", failing_request).await, Err(Error::DeadlineExceeded)));
+        // A huge base is cut short by the deadline.
+        let rest = builder("http://invalid-url")
+            .retries(2)
+            .retry_if(|_, _| true)
+            .retry_backoff(Duration::MAX)
+            .build().unwrap()
+            .with_deadline(std::time::Instant::now() + Duration::from_millis(50));
+        assert!(matches!(rest.with_retry("tx/00", failing_request).await, Err(Error::DeadlineExceeded)));
+    }
+    #[tokio::test]
+    async fn max_concurrent_requests() {
+        let rest = builder("http://invalid-url").max_concurrent_requests(2).build().unwrap();
+        let cloned = rest.clone();
+        let permit = rest.acquire().await;
+        assert!(permit.is_some());
+        assert_eq!(cloned.semaphore.as_ref().unwrap().available_permits(), 1);
+        drop(permit);
+        assert!(rest.blockhashbyheight(0).await.is_err());
+        assert_eq!(cloned.semaphore.as_ref().unwrap().available_permits(), 2);
+        assert!(try_new("http://invalid-url").unwrap().acquire().await.is_none());
+    }
+    #[tokio::test]
+    async fn with_deadline() {
+        let rest = try_new("http://invalid-url").unwrap();
+        let now = std::time::Instant::now();
+        let expired = rest.with_deadline(now);
+        assert!(matches!(expired.chaininfo().await, Err(Error::DeadlineExceeded)));
+        assert!(matches!(expired.with_deadline(now + Duration::from_secs(60)).chaininfo().await, Err(Error::DeadlineExceeded)));
+        assert!(!matches!(rest.chaininfo().await, Err(Error::DeadlineExceeded)));
+    }
+    #[test]
+    fn url_ipv6() {
+        let rest = try_new("http://[::1]:18443/rest/").unwrap();
+        let url = rest.url("chaininfo", "json").unwrap();
+        assert_eq!(url, "http://[::1]:18443/rest/chaininfo.json");
+        let url = reqwest::Url::parse(&url).unwrap();
+        assert_eq!(url.host_str(), Some("[::1]"));
+        assert_eq!(url.port(), Some(18443));
+        assert_eq!(url.path(), "/rest/chaininfo.json");
+    }
+    #[test]
+    fn base_url() {
+        let rest = try_new(DEFAULT_ENDPOINT).unwrap();
+        assert_eq!(rest.base_url().as_str(), "http://localhost:8332/rest/");
+        assert_eq!(rest.base_url().port(), Some(8332));
+        assert_eq!(try_new("http://localhost:8332/rest/").unwrap().base_url(), rest.base_url());
+        assert!(matches!(try_new("not a url"), Err(Error::InvalidEndpoint { .. })));
+        assert!(matches!(builder("not a url").build(), Err(Error::InvalidEndpoint { .. })));
+        assert!(matches!(builder("mailto:rest@localhost").build(), Err(Error::InvalidEndpoint { .. })));
+    }
+    /// Needs a regtest node listening on IPv6 at `BITCOIN_REST_IPV6_ENDPOINT` (e.g. "http://[::1]:18443/rest/").
+    /// See `url_ipv6` for the part which needs no node.
+    #[tokio::test]
+    #[ignore = "needs a regtest node listening on IPv6 at BITCOIN_REST_IPV6_ENDPOINT"]
+    async fn regtest_ipv6() {
+        let test_endpoint = std::env::var("BITCOIN_REST_IPV6_ENDPOINT")
+            .expect("set BITCOIN_REST_IPV6_ENDPOINT to the REST endpoint of a regtest node listening on IPv6");
+        let rest = try_new(&test_endpoint).unwrap();
+        let chaininfo = rest.chaininfo().await.unwrap();
+        assert_eq!(chaininfo.chain, "regtest");
+    }
+    struct Fixture {
+        rest_env_name: &'static str,
+        genesis_block_hash: &'static str,
+        txid_coinbase_block1: &'static str,
+    }
+    async fn decode_fail(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        assert!(rest.blockhashbyheight(0xFFFFFFFF).await.is_err());
+    }
+    async fn connect(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        assert!(super::connect(&test_endpoint).await.is_ok());
+    }
+    async fn tx(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let tx = rest.tx(&Txid::from_str(f.txid_coinbase_block1).unwrap()).await.unwrap();
+        assert_eq!(tx.txid().to_string(), f.txid_coinbase_block1);
+    }
+    async fn txout(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let txid = Txid::from_str(f.txid_coinbase_block1).unwrap();
+        let txout = rest.txout(&OutPoint::new(txid, 0)).await.unwrap().unwrap();
+        assert!(txout.value > 0);
+        assert!(rest.txout(&OutPoint::new(txid, 100)).await.unwrap().is_none());
+        if let Some(utxo) = rest.unspent_txout(&OutPoint::new(txid, 0)).await.unwrap() {
+            assert_eq!(utxo.value, Amount::from_sat(txout.value));
+        }
+        assert!(rest.unspent_txout(&OutPoint::new(txid, 100)).await.unwrap().is_none());
+    }
+    async fn tx_no_witness(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let tx = rest.tx_no_witness(&Txid::from_str(f.txid_coinbase_block1).unwrap()).await.unwrap();
+        assert_eq!(tx.txid().to_string(), f.txid_coinbase_block1);
+        assert!(tx.input.iter().all(|input| input.witness.is_empty()));
+        assert_eq!(tx.wtxid().as_inner(), tx.txid().as_inner());
+    }
+    async fn block(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
+        let block = rest.block(&blockid).await.unwrap();
+        assert_eq!(block.block_hash().to_string(), f.genesis_block_hash);
+    }
+    #[tokio::test]
+    async fn deadline_exceeded() {
+        let never = futures::future::pending::<Result<(), Error>>();
+        let result = run_with_deadline(Some(Duration::from_millis(10)), never).await;
+        assert!(matches!(result, Err(Error::DeadlineExceeded)));
+        assert!(run_with_deadline(None, async { Ok(()) }).await.is_ok());
+    }
+    async fn blocks(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
+        let blocks = rest.blocks(&[blockid, blockid], 2, Some(Duration::from_secs(60))).await.unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert!(blocks.iter().all(|block| block.block_hash() == blockid));
+        let txid = Txid::from_str(f.txid_coinbase_block1).unwrap();
+        let txs = rest.txs(&[txid], 1, None).await.unwrap();
+        assert_eq!(txs[0].txid(), txid);
+    }
+    async fn miner_revenue(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
+        assert_eq!(rest.miner_revenue(&blockid).await.unwrap(), Amount::from_btc(50.0).unwrap());
+    }
+    #[test]
+    fn block_subsidy() {
+        let interval = subsidy_halving_interval(Network::Bitcoin);
+        assert_eq!(super::block_subsidy(0, interval), Amount::from_btc(50.0).unwrap());
+        assert_eq!(super::block_subsidy(209_999, interval), Amount::from_btc(50.0).unwrap());
+        assert_eq!(super::block_subsidy(210_000, interval), Amount::from_btc(25.0).unwrap());
+        assert_eq!(super::block_subsidy(840_000, interval), Amount::from_btc(3.125).unwrap());
+        assert_eq!(super::block_subsidy(SUBSIDY_HALVING_INTERVAL * 33, interval), Amount::ZERO);
+        assert_eq!(super::block_subsidy(u32::MAX, interval), Amount::ZERO);
+        let interval = subsidy_halving_interval(Network::Regtest);
+        assert_eq!(super::block_subsidy(149, interval), Amount::from_btc(50.0).unwrap());
+        assert_eq!(super::block_subsidy(150, interval), Amount::from_btc(25.0).unwrap());
+    }
+    #[tokio::test]
+    async fn subsidy_halving_interval_override() {
+        // Answered without contacting the node.
+        let rest = builder("http://invalid-url").subsidy_halving_interval(1_051_200).build().unwrap();
+        assert_eq!(rest.subsidy_halving_interval().await.unwrap(), 1_051_200);
+        assert_eq!(super::block_subsidy(1_051_200, 1_051_200), Amount::from_btc(25.0).unwrap());
+        let mut params = Params::new(Network::Regtest);
+        params.pow_target_spacing = 90;
+        let rest = builder("http://invalid-url").params(params).build().unwrap();
+        assert_eq!(rest.subsidy_halving_interval().await.unwrap(), 150);
+    }
+    async fn block_fees(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
+        assert_eq!(rest.block_fees(&blockid, 0).await.unwrap(), Amount::ZERO);
+        // The miner of block 501726 claimed nothing.
+        let blockid = rest.blockhashbyheight(501726).await.unwrap();
+        assert_eq!(rest.block_fees(&blockid, 501726).await.unwrap(), Amount::ZERO);
+    }
+    async fn export_blocks(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+        let summary = rest.export_blocks(0, 10, tx, 4).await;
+        assert!(summary.is_ok());
+        assert_eq!(summary.exported, 10);
+        let mut heights = Vec::new();
+        while let Some((height, block)) = rx.recv().await {
+            if height == 0 {
+                assert_eq!(block.block_hash().to_string(), f.genesis_block_hash);
+            }
+            heights.push(height);
+        }
+        heights.sort_unstable();
+        assert_eq!(heights, (0..10).collect::<Vec<_>>());
+    }
+    async fn blocks_in_order(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let blocks: Vec<(u32, Block)> = rest.blocks_in_order(0, 10, 4).try_collect().await.unwrap();
+        assert_eq!(blocks.iter().map(|(height, _)| *height).collect::<Vec<_>>(), (0..10).collect::<Vec<_>>());
+        assert_eq!(blocks[0].1.block_hash().to_string(), f.genesis_block_hash);
+        for pair in blocks.windows(2) {
+            assert_eq!(pair[1].1.header.prev_blockhash, pair[0].1.block_hash());
+        }
+    }
+    async fn blocks_at_heights(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let results = rest.blocks_at_heights(&[2, 0, u32::MAX], 2).await;
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().header.prev_blockhash, rest.blockhashbyheight(1).await.unwrap());
+        assert_eq!(results[1].as_ref().unwrap().block_hash().to_string(), f.genesis_block_hash);
+        assert!(results[2].is_err());
+    }
+    async fn block_hex_to(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
+        let mut hex = Vec::new();
+        let written = rest.block_hex_to(&blockid, &mut hex).await.unwrap();
+        assert_eq!(written, hex.len() as u64);
+        let expected = rest.call_hex(&["block", f.genesis_block_hash].join("/")).await.unwrap();
+        assert_eq!(String::from_utf8(hex).unwrap(), expected);
+    }
+    async fn block_output_value(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
+        assert_eq!(rest.block_output_value(&blockid).await.unwrap(), Amount::from_btc(50.0).unwrap());
+    }
+    #[test]
+    fn output_values_overflow() {
+        let output = |value| TxOut { value, script_pubkey: bitcoin::Script::new() };
+        let outputs = vec![output(1), output(2)];
+        assert_eq!(sum_output_values(&outputs).unwrap(), Amount::from_sat(3));
+        let outputs = vec![output(u64::MAX), output(1)];
+        assert!(matches!(sum_output_values(&outputs), Err(Error::AmountOverflow)));
+    }
+    #[test]
+    fn block_utxo_delta() {
+        let mut block = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Bitcoin);
+        let coinbase_txid = block.txdata[0].txid();
+        let mut tx = block.txdata[0].clone();
+        tx.input[0].previous_output = OutPoint::new(coinbase_txid, 0);
+        tx.output.push(TxOut {
+            value: 0,
+            script_pubkey: bitcoin::blockdata::script::Builder::new()
+                .push_opcode(bitcoin::blockdata::opcodes::all::OP_RETURN)
+                .into_script(),
+        });
+        let txid = tx.txid();
+        block.txdata.push(tx);
+        let (created, spent) = utxo_delta(&block);
+        assert_eq!(created, vec![OutPoint::new(coinbase_txid, 0), OutPoint::new(txid, 0)]);
+        assert_eq!(spent, vec![OutPoint::new(coinbase_txid, 0)]);
+    }
+    #[test]
+    fn block_outputs() {
+        let mut block = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Bitcoin);
+        let coinbase_txid = block.txdata[0].txid();
+        let mut tx = block.txdata[0].clone();
+        tx.output.insert(0, TxOut {
+            value: 0,
+            script_pubkey: bitcoin::blockdata::script::Builder::new()
+                .push_opcode(bitcoin::blockdata::opcodes::all::OP_RETURN)
+                .into_script(),
+        });
+        let txid = tx.txid();
+        block.txdata.push(tx.clone());
+        let outputs = super::block_outputs(&block);
+        assert_eq!(outputs.len(), 3);
+        assert_eq!(outputs[0], (OutPoint::new(coinbase_txid, 0), block.txdata[0].output[0].clone()));
+        assert_eq!(outputs[1], (OutPoint::new(txid, 0), tx.output[0].clone()));
+        assert_eq!(outputs[2], (OutPoint::new(txid, 1), tx.output[1].clone()));
+    }
+    #[test]
+    fn block_integrity_check() {
+        let block = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Bitcoin);
+        let blockhash = block.block_hash();
+        assert!(BlockIntegrity::check(&block, &blockhash).is_ok());
+        let mut tampered = block.clone();
+        tampered.txdata[0].output[0].value += 1;
+        assert_eq!(BlockIntegrity::check(&tampered, &blockhash), BlockIntegrity {
+            hash_matches: true,
+            merkle_root_matches: false,
+            pow_valid: true,
+        });
+        let mut tampered = block;
+        tampered.header.nonce += 1;
+        let integrity = BlockIntegrity::check(&tampered, &blockhash);
+        assert!(!integrity.hash_matches);
+        assert!(integrity.merkle_root_matches);
+        assert!(!integrity.pow_valid);
+    }
+    async fn block_integrity(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
+        assert!(rest.block_integrity(&blockid).await.unwrap().is_ok());
+    }
+    #[test]
+    fn block_coinbase_message() {
+        let block = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Bitcoin);
+        let message = String::from_utf8_lossy(&super::coinbase_message(&block)).into_owned();
+        assert!(message.ends_with("The Times 03/Jan/2009 Chancellor on brink of second bailout for banks"));
+        let mut block = block;
+        block.txdata.clear();
+        assert!(super::coinbase_message(&block).is_empty());
+    }
+    async fn coinbase_message(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
+        let message = rest.coinbase_message(&blockid).await.unwrap();
+        assert!(!message.is_empty());
+        let lossy = rest.coinbase_message_lossy(&blockid).await.unwrap();
+        assert_eq!(lossy, String::from_utf8_lossy(&message));
+    }
+    #[test]
+    fn block_coinbase_height() {
+        let mut block = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Bitcoin);
+        assert_eq!(super::coinbase_height(&block), None);
+        block.header.version = 2;
+        let mut set_script_sig = |script: Vec<u8>| {
+            block.txdata[0].input[0].script_sig = bitcoin::Script::from(script);
+            super::coinbase_height(&block)
+        };
+        assert_eq!(set_script_sig(vec![0x03, 0x5b, 0x7a, 0x03, 0xff]), Some(227931));
+        assert_eq!(set_script_sig(vec![0x51]), Some(1));
+        assert_eq!(set_script_sig(vec![0x01, 0x80]), None);
+        assert_eq!(set_script_sig(vec![0x03, 0x5b]), None);
+        assert_eq!(set_script_sig(vec![]), None);
+    }
+    async fn genesis_coinbase_message(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let message = rest.genesis_coinbase_message().await.unwrap();
+        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
+        assert_eq!(message, rest.coinbase_message(&blockid).await.unwrap());
+        assert!(String::from_utf8_lossy(&message).ends_with("Chancellor on brink of second bailout for banks"));
+    }
+    async fn coinbase_height(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let genesis = BlockHash::from_str(f.genesis_block_hash).unwrap();
+        assert_eq!(rest.coinbase_height(&genesis).await.unwrap(), None);
+        let blockhash = rest.blockhashbyheight(227931).await.unwrap();
+        assert_eq!(rest.coinbase_height(&blockhash).await.unwrap(), Some(227931));
+    }
+    #[test]
+    fn block_witness_commitment() {
+        let mut block = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Bitcoin);
+        assert_eq!(super::witness_commitment(&block), None);
+        let commitment_output = |commitment: [u8; 32]| {
+            let mut script = vec![0x6a, 0x24, 0xaa, 0x21, 0xa9, 0xed];
+            script.extend_from_slice(&commitment);
+            TxOut { value: 0, script_pubkey: bitcoin::Script::from(script) }
+        };
+        block.txdata[0].output.push(commitment_output([1u8; 32]));
+        assert_eq!(super::witness_commitment(&block), Some([1u8; 32]));
+        // The last matching output wins.
+        block.txdata[0].output.push(commitment_output([2u8; 32]));
+        assert_eq!(super::witness_commitment(&block), Some([2u8; 32]));
+    }
+    async fn witness_commitment(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
+        assert_eq!(rest.witness_commitment(&blockid).await.unwrap(), None);
+    }
+    #[test]
+    fn genesis_check_pow() {
+        let mut header = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Bitcoin).header;
+        assert!(check_pow(&header));
+        header.nonce += 1;
+        assert!(!check_pow(&header));
+        // Regtest has the easiest target.
+        let header = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Regtest).header;
+        assert!(check_pow(&header));
+    }
+    #[test]
+    fn block_summary() {
+        let block = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Bitcoin);
+        let summary = BlockSummary::from(&block);
+        assert_eq!(summary.hash.to_string(), "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f");
+        assert_eq!(summary.prev_blockhash, BlockHash::from_inner([0u8; 32]));
+        assert_eq!(summary.merkle_root, block.header.merkle_root);
+        assert_eq!(summary.time, 1231006505);
+        assert_eq!(summary.bits, 0x1d00ffff);
+        assert_eq!(summary.nonce, 2083236893);
+        assert_eq!(summary.tx_count, 1);
+        // The genesis block has no witness, so the weight is 4 times its size.
+        assert_eq!(summary.weight, bitcoin::consensus::serialize(&block).len() * 4);
+    }
+    #[test]
+    fn compact_block_from_block() {
+        let mut block = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Bitcoin);
+        let coinbase = block.txdata[0].clone();
+        let compact_block = HeaderAndShortIds::from_block(&block, 42);
+        assert!(compact_block.short_ids.is_empty());
+        assert_eq!(compact_block.prefilled_txs, vec![PrefilledTransaction { index: 0, tx: coinbase.clone() }]);
+        let size = bitcoin::consensus::serialize(&compact_block).len();
+        assert_eq!(size, 80 + 8 + 1 + 1 + 1 + bitcoin::consensus::serialize(&coinbase).len());
+        block.txdata.push(coinbase.clone());
+        let compact_block = HeaderAndShortIds::from_block(&block, 42);
+        assert_eq!(compact_block.short_ids.len(), 1);
+        // The short ids depend on the nonce.
+        assert_ne!(compact_block.short_ids, HeaderAndShortIds::from_block(&block, 43).short_ids);
+        let size = bitcoin::consensus::serialize(&compact_block).len();
+        assert_eq!(size, 80 + 8 + 1 + 6 + 1 + 1 + bitcoin::consensus::serialize(&coinbase).len());
+    }
+    async fn compact_block(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
+        let compact_block = rest.compact_block(&blockid, 0).await.unwrap();
+        assert_eq!(compact_block.header.block_hash(), blockid);
+        assert_eq!(compact_block.prefilled_txs.len(), 1);
+    }
+    async fn block_notxdetails(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
+        let blockheader = rest.block_notxdetails(&blockid).await.unwrap();
+        assert_eq!(blockheader.block_hash().to_string(), f.genesis_block_hash);
+    }
+    fn reversed_hash_bytes(hex: &str) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for i in 0..32 {
+            bytes[31 - i] = u8::from_str_radix(&hex[(2 * i)..(2 * i + 2)], 16).unwrap();
+        }
+        bytes
+    }
+    #[test]
+    fn hash_bytes_order() {
+        let genesis = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+        let bytes = reversed_hash_bytes(genesis);
+        assert_eq!(bytes[0], 0x6f);
+        assert_eq!(BlockHash::from_inner(bytes).to_string(), genesis);
+    }
+    async fn by_bytes(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let tx = rest.tx_by_bytes(&reversed_hash_bytes(f.txid_coinbase_block1)).await.unwrap();
+        assert_eq!(tx.txid().to_string(), f.txid_coinbase_block1);
+        let block = rest.block_by_bytes(&reversed_hash_bytes(f.genesis_block_hash)).await.unwrap();
+        assert_eq!(block.block_hash().to_string(), f.genesis_block_hash);
+        let blockheader = rest.block_notxdetails_by_bytes(&reversed_hash_bytes(f.genesis_block_hash)).await.unwrap();
+        assert_eq!(blockheader.block_hash().to_string(), f.genesis_block_hash);
+    }
+    async fn headers(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
+        let headers = rest.headers(1, &blockid).await.unwrap();
+        assert_eq!(headers[0].block_hash().to_string(), f.genesis_block_hash);
+        let headers = rest.headers(MAX_HEADERS_RESULTS, &blockid).await.unwrap();
+        assert_eq!(headers.len(), MAX_HEADERS_RESULTS as usize);
+        let result = rest.headers(MAX_HEADERS_RESULTS + 1, &blockid).await;
+        assert!(matches!(result, Err(Error::Http { status: reqwest::StatusCode::BAD_REQUEST, .. })));
+    }
+    async fn headers_directional(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
+        let forward = rest.headers_directional(5, &blockid, Direction::Forward).await.unwrap();
+        assert_eq!(forward.len(), 5);
+        let backward = rest.headers_directional(5, &forward[4].block_hash(), Direction::Backward).await.unwrap();
+        assert_eq!(backward, forward.iter().rev().cloned().collect::<Vec<_>>());
+        // Stops at genesis.
+        let backward = rest.headers_directional(10, &forward[2].block_hash(), Direction::Backward).await.unwrap();
+        assert_eq!(backward.len(), 3);
+        assert_eq!(backward[2].block_hash().to_string(), f.genesis_block_hash);
+    }
+    #[test]
+    fn header_chain() {
+        let genesis = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Bitcoin).header;
+        let mut next = genesis;
+        next.prev_blockhash = genesis.block_hash();
+        let zero = BlockHash::from_inner([0u8; 32]);
+        assert!(verify_header_chain(&[], &zero).is_ok());
+        assert!(verify_header_chain(&[genesis, next], &zero).is_ok());
+        let result = verify_header_chain(&[genesis, next], &genesis.block_hash());
+        assert!(matches!(result, Err(Error::HeaderChainMismatch { index: 0 })));
+        let result = verify_header_chain(&[genesis, next, next], &zero);
+        assert!(matches!(result, Err(Error::HeaderChainMismatch { index: 2 })));
+    }
+    async fn headers_verified(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
+        let zero = BlockHash::from_inner([0u8; 32]);
+        let headers = rest.headers_verified(10, &blockid, &zero).await.unwrap();
+        assert_eq!(headers.len(), 10);
+        let result = rest.headers_verified(10, &blockid, &blockid).await;
+        assert!(matches!(result, Err(Error::HeaderChainMismatch { index: 0 })));
+    }
+    #[test]
+    fn headers_iter_decode() {
+        let genesis = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Bitcoin).header;
+        let mut bytes = bitcoin::consensus::serialize(&genesis);
+        bytes.extend(bitcoin::consensus::serialize(&genesis));
+        bytes.extend(&[0u8; 10]);
+        let mut iter = HeadersIter { path: "headers".to_string(), bytes: bytes.into(), offset: 0 };
+        assert_eq!(iter.size_hint(), (3, Some(3)));
+        assert_eq!(iter.next().unwrap().unwrap().block_hash(), genesis.block_hash());
+        assert_eq!(iter.next().unwrap().unwrap().block_hash(), genesis.block_hash());
+        let err = iter.next().unwrap().unwrap_err();
+        assert!(format!("{}", err).contains("headers"));
+        assert!(iter.next().is_none());
+    }
+    async fn headers_check_pow(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
+        for header in rest.headers(10, &blockid).await.unwrap() {
+            assert!(check_pow(&header));
+        }
+    }
+    async fn all_headers(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let tip_height = rest.chaininfo().await.unwrap().blocks as usize;
+        let headers = rest.all_headers().await.unwrap();
+        assert!(headers.len() > tip_height);
+        assert!(headers.len() <= rest.chaininfo().await.unwrap().blocks as usize + 1);
+        assert_eq!(headers[0].block_hash().to_string(), f.genesis_block_hash);
+        assert!(verify_header_chain(&headers, &BlockHash::from_inner([0u8; 32])).is_ok());
+    }
+    async fn headers_iter(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let blockid = BlockHash::from_str(f.genesis_block_hash).unwrap();
+        let mut prev = blockid;
+        for (i, header) in rest.headers_iter(5, &blockid).await.unwrap().enumerate() {
+            let header = header.unwrap();
+            if i > 0 {
+                assert_eq!(header.prev_blockhash, prev);
+            }
+            prev = header.block_hash();
+        }
     }
     async fn chaininfo(f: &Fixture) {
         let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
-        let rest = new(&test_endpoint);
+        let rest = try_new(&test_endpoint).unwrap();
         let chaininfo = rest.chaininfo().await.unwrap();
         assert_eq!(chaininfo.chain, "main");
     }
+    #[test]
+    fn chaininfo_softforks() {
+        let chaininfo = |softforks: &str| -> ChainInfo {
+            serde_json::from_str(&format!(r#"{{
+                "chain": "main", "blocks": 1, "headers": 1, "bestblockhash": "00",
+                "difficulty": 1.0, "mediantime": 0, "verificationprogress": 1.0,
+                "chainwork": "00", "pruned": false, {} "warnings": ""
+            }}"#, softforks)).unwrap()
+        };
+        let softforks = chaininfo(r#""softforks": {"csv": {"type": "buried", "active": true, "height": 419328}},"#)
+            .softforks.unwrap();
+        assert_eq!(softforks["csv"].height, 419328);
+        assert!(chaininfo(r#""softforks": [{"id": "bip34", "version": 2}],"#).softforks.is_none());
+        assert!(chaininfo("").softforks.is_none());
+    }
+    #[test]
+    fn deploymentinfo_sample() {
+        let deploymentinfo: DeploymentInfo = serde_json::from_str(r#"{
+            "hash": "0000000000000000000320283a032748cef8227873ff4872689bf23f1cda83a5", "height": 840000,
+            "deployments": {
+                "segwit": {"type": "buried", "active": true, "height": 481824},
+                "taproot": {"type": "bip9", "height": 709632, "active": true,
+                    "bip9": {"start_time": 1619222400, "timeout": 1628640000, "min_activation_height": 709632,
+                        "status": "active", "since": 709632, "status_next": "active"}}
+            }
+        }"#).unwrap();
+        assert_eq!(deploymentinfo.height, 840000);
+        assert!(deploymentinfo.deployments["segwit"].active);
+        assert_eq!(deploymentinfo.deployments["taproot"].height, 709632);
+    }
+    /// The directory of the responses recorded from real nodes by `capture_samples`.
+    fn recorded_dir() -> std::path::PathBuf {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/testdata/recorded")
+    }
+    /// Record the responses checked by `recorded_samples` from the node at `BITCOIN_REST_ENDPOINT`
+    /// into `src/testdata/recorded/<endpoint>_v<BITCOIN_CORE_VERSION>.json`.
+    ///
+    /// Only the first few mempool entries are kept so that the files stay small.
+    #[tokio::test]
+    #[ignore = "needs a live node and BITCOIN_CORE_VERSION; writes into src/testdata/recorded"]
+    async fn capture_samples() {
+        const MAX_MEMPOOL_ENTRIES: usize = 3;
+        let version = std::env::var("BITCOIN_CORE_VERSION").expect("set BITCOIN_CORE_VERSION to the node's version, e.g. 27.0");
+        let test_endpoint = std::env::var("BITCOIN_REST_ENDPOINT").unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let block1 = rest.block_at_height(1).await.unwrap();
+        let paths = vec![
+            ("chaininfo", "chaininfo".to_string()),
+            ("mempool_info", "mempool/info".to_string()),
+            ("mempool_contents", "mempool/contents".to_string()),
+            ("getutxos", format!("getutxos/checkmempool/{}-0", block1.txdata[0].txid())),
+        ];
+        for (name, path) in paths {
+            let mut body = rest.call_text(&path, "json").await.unwrap();
+            if name == "mempool_contents" {
+                let contents: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&body).unwrap();
+                let contents: serde_json::Map<_, _> = contents.into_iter().take(MAX_MEMPOOL_ENTRIES).collect();
+                body = serde_json::to_string(&contents).unwrap();
+            }
+            std::fs::write(recorded_dir().join(format!("{}_v{}.json", name, version)), body).unwrap();
+        }
+    }
+    /// Check that every field present in both `raw` and `parsed` has the same value.
+    ///
+    /// A field which fails to populate (e.g. because of a naming mismatch) shows up as a default value in `parsed`.
+    /// Fields normalized into another JSON type (e.g. `warnings`) are skipped.
+    fn assert_common_fields_eq(path: &str, raw: &serde_json::Value, parsed: &serde_json::Value) {
+        use serde_json::Value;
+        match (raw, parsed) {
+            (Value::Object(raw), Value::Object(parsed)) => {
+                for (key, value) in parsed {
+                    if let Some(raw_value) = raw.get(key) {
+                        assert_common_fields_eq(&format!("{}.{}", path, key), raw_value, value);
+                    }
+                }
+            },
+            (Value::Array(raw), Value::Array(parsed)) => {
+                assert_eq!(raw.len(), parsed.len(), "{}", path);
+                for (index, (raw, parsed)) in raw.iter().zip(parsed).enumerate() {
+                    assert_common_fields_eq(&format!("{}[{}]", path, index), raw, parsed);
+                }
+            },
+            (Value::Number(raw), Value::Number(parsed)) => assert_eq!(raw.as_f64(), parsed.as_f64(), "{}", path),
+            (Value::String(raw), Value::String(parsed)) => assert_eq!(raw, parsed, "{}", path),
+            (Value::Bool(raw), Value::Bool(parsed)) => assert_eq!(raw, parsed, "{}", path),
+            _ => {},
+        }
+    }
+    fn assert_recorded<T: for<'de> Deserialize<'de> + Serialize>(name: &str, body: &str) {
+        let parsed: T = serde_json::from_str(body).unwrap_or_else(|err| panic!("{}: {}", name, err));
+        let raw: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_common_fields_eq(name, &raw, &serde_json::to_value(&parsed).unwrap());
+    }
+    #[test]
+    #[ignore = "no recorded samples are committed yet; run capture_samples against real nodes first"]
+    fn recorded_samples() {
+        let mut checked = 0;
+        for entry in std::fs::read_dir(recorded_dir()).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().map_or(true, |ext| ext != "json") {
+                continue;
+            }
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            let body = std::fs::read_to_string(&path).unwrap();
+            match name.rsplitn(2, "_v").nth(1) {
+                Some("chaininfo") => assert_recorded::<ChainInfo>(&name, &body),
+                Some("mempool_info") => assert_recorded::<MempoolInfo>(&name, &body),
+                Some("mempool_contents") => assert_recorded::<HashMap<String, MempoolEntry>>(&name, &body),
+                Some("getutxos") => assert_recorded::<UtxoData>(&name, &body),
+                _ => panic!("unexpected recorded sample: {}", name),
+            }
+            checked += 1;
+        }
+        assert!(checked > 0, "no recorded samples in {}", recorded_dir().display());
+    }
+    // The samples are synthetic; see src/testdata/README.md.
+    #[test]
+    fn chaininfo_samples() {
+        let chaininfo: ChainInfo = serde_json::from_str(include_str!("testdata/chaininfo_softforks_map.json")).unwrap();
+        assert_eq!(chaininfo.chain, "main");
+        assert_eq!(chaininfo.blocks, 680000);
+        assert_eq!(chaininfo.headers, 680000);
+        assert_eq!(chaininfo.bestblockhash, "000000000000000000076c036ff5119e5a5a74df77abf64203473364509f7732");
+        assert_eq!(chaininfo.time, 0);
+        assert_eq!(chaininfo.mediantime, 1618354766);
+        assert!(!chaininfo.in_ibd());
+        assert!(chaininfo.verificationprogress > 0.99);
+        assert!(!chaininfo.pruned);
+        assert_eq!(chaininfo.pruneheight, 0);
+        let softforks = chaininfo.softforks.unwrap();
+        assert_eq!(softforks["segwit"].height, 481824);
+        assert_eq!(softforks["taproot"].type_, "bip9");
+        assert!(!softforks["taproot"].active);
+        assert_eq!(chaininfo.warnings, "");
+        let chaininfo: ChainInfo = serde_json::from_str(include_str!("testdata/chaininfo_warnings_array_pruned.json")).unwrap();
+        assert_eq!(chaininfo.time, 1728000000);
+        assert!(!chaininfo.in_ibd());
+        assert!(chaininfo.pruned);
+        assert_eq!(chaininfo.pruneheight, 864000);
+        assert!(chaininfo.softforks.is_none());
+        assert_eq!(chaininfo.warnings, "This is a pre-release test build - use at your own risk");
+    }
+    #[test]
+    fn chaininfo_typed_accessors() {
+        let mut chaininfo: ChainInfo = serde_json::from_str(include_str!("testdata/chaininfo_softforks_map.json")).unwrap();
+        assert_eq!(chaininfo.mediantime_datetime(), std::time::UNIX_EPOCH + Duration::from_secs(1618354766));
+        assert_eq!(chaininfo.time_datetime(), None);
+        assert!(chaininfo.verification_percent() > 99.0 && chaininfo.verification_percent() <= 100.0);
+        // Bitcoin Core may report a progress slightly above 1.
+        chaininfo.verificationprogress = 1.000001;
+        assert_eq!(chaininfo.verification_percent(), 100.0);
+        let chaininfo: ChainInfo = serde_json::from_str(include_str!("testdata/chaininfo_warnings_array_pruned.json")).unwrap();
+        assert_eq!(chaininfo.time_datetime(), Some(std::time::UNIX_EPOCH + Duration::from_secs(1728000000)));
+    }
+    #[test]
+    fn chaininfo_is_available_at() {
+        let chaininfo: ChainInfo = serde_json::from_str(include_str!("testdata/chaininfo_softforks_map.json")).unwrap();
+        assert!(chaininfo.is_available_at(0));
+        assert!(chaininfo.is_available_at(680000));
+        assert!(!chaininfo.is_available_at(680001));
+        let chaininfo: ChainInfo = serde_json::from_str(include_str!("testdata/chaininfo_warnings_array_pruned.json")).unwrap();
+        assert!(!chaininfo.is_available_at(0));
+        assert!(!chaininfo.is_available_at(863999));
+        assert!(chaininfo.is_available_at(864000));
+        assert!(chaininfo.is_available_at(865000));
+    }
+    #[test]
+    fn mempool_info_sample() {
+        let info: MempoolInfo = serde_json::from_str(include_str!("testdata/mempool_info.json")).unwrap();
+        assert!(info.loaded);
+        assert_eq!(info.size, 3127);
+        assert_eq!(info.bytes, 1498327);
+        assert_eq!(info.maxmempool, 300000000);
+        assert_eq!(info.mempoolminfee, Amount::from_sat(1000));
+    }
+    #[test]
+    fn mempool_contents_samples() {
+        let txid = "e2d0f4bc8cbd06a2b2b0c8ccee3d8ca6a2e63c6a7a1e4f7c7b3be9b5e9c3c6a1";
+        let contents: HashMap<String, MempoolEntry> = serde_json::from_str(include_str!("testdata/mempool_contents_fees_object.json")).unwrap();
+        let entry = &contents[txid];
+        assert_eq!(entry.vsize, 141);
+        assert_eq!(entry.weight, 561);
+        assert_eq!(entry.height, 800000);
+        assert_eq!(entry.fees.base, Amount::from_sat(1410));
+        assert_eq!(entry.fees.modified, SignedAmount::from_sat(1410));
+        assert_eq!(entry.fees.ancestor, SignedAmount::from_sat(1410));
+        assert_eq!(entry.fees.descendant, SignedAmount::from_sat(1410));
+        assert_eq!(entry.ancestorfees, None);
+        assert_eq!(entry.descendantfees, None);
+        assert!(entry.bip125_replaceable);
+        let contents: HashMap<String, MempoolEntry> = serde_json::from_str(include_str!("testdata/mempool_contents_legacy_fees.json")).unwrap();
+        let entry = &contents[txid];
+        assert_eq!(entry.ancestorcount, 2);
+        assert_eq!(entry.fees.ancestor, SignedAmount::from_sat(2820));
+        assert_eq!(entry.ancestorfees, Some(SignedAmount::from_sat(2820)));
+        assert_eq!(entry.descendantfees, Some(SignedAmount::from_sat(1410)));
+        assert_eq!(entry.depends.len(), 1);
+        assert!(!entry.bip125_replaceable);
+    }
+    #[test]
+    fn mempool_txids() {
+        let txid = "e2d0f4bc8cbd06a2b2b0c8ccee3d8ca6a2e63c6a7a1e4f7c7b3be9b5e9c3c6a1";
+        let txids: MempoolTxids = Context::parse_json("mempool/contents", &format!(r#"["{}"]"#, txid)).unwrap();
+        assert!(txids.contains(txid));
+        assert!(!txids.contains(&"00".repeat(32)));
+        // Nodes which ignore verbose=false return the verbose entries.
+        let txids: MempoolTxids = Context::parse_json("mempool/contents", include_str!("testdata/mempool_contents_fees_object.json")).unwrap();
+        assert!(txids.contains(txid));
+        let txids: MempoolTxids = Context::parse_json("mempool/contents", "[]").unwrap();
+        assert!(!txids.contains(txid));
+    }
+    #[test]
+    fn getutxos_samples() {
+        let utxos: UtxoData = serde_json::from_str(include_str!("testdata/getutxos_legacy_addresses.json")).unwrap();
+        assert_eq!(utxos.chain_height, 680000);
+        assert_eq!(utxos.chaintip_hash, "000000000000000000076c036ff5119e5a5a74df77abf64203473364509f7732");
+        assert_eq!(utxos.bitmap, "1");
+        let script_pub_key = &utxos.utxos[0].script_pub_key;
+        assert_eq!(utxos.utxos[0].value, Amount::from_sat(5_000_000_000));
+        assert_eq!(script_pub_key.req_sigs, 1);
+        assert_eq!(script_pub_key.type_, "pubkey");
+        assert_eq!(script_pub_key.addresses, vec!["12c6DSiU4Rq3P4ZxziKxzrGaYg7C4xkCRT"]);
+        assert!(script_pub_key.asm.ends_with("OP_CHECKSIG"));
+        assert!(script_pub_key.hex.ends_with("ac"));
+        let utxos: UtxoData = serde_json::from_str(include_str!("testdata/getutxos_address.json")).unwrap();
+        assert_eq!(utxos.bitmap, "011");
+        assert_eq!(utxos.utxos.len(), 2);
+        assert_eq!(utxos.utxos[1].height, 700000);
+        assert_eq!(utxos.utxos[1].value, Amount::from_sat(12345));
+        assert_eq!(utxos.utxos[1].script_pub_key.type_, "pubkeyhash");
+        assert_eq!(utxos.utxos[1].script_pub_key.address.as_deref(), Some("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"));
+    }
+    async fn chaininfo_raw(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let raw = rest.chaininfo_raw().await.unwrap();
+        let chaininfo: ChainInfo = serde_json::from_str(&raw).unwrap();
+        assert_eq!(chaininfo.chain, "main");
+    }
     async fn blockhashbyheight(f: &Fixture) {
         let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
-        let rest = new(&test_endpoint);
+        let rest = try_new(&test_endpoint).unwrap();
         assert_eq!(rest.blockhashbyheight(0).await.unwrap().to_string(), f.genesis_block_hash);
     }
+    async fn decode_at_height(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let block: Block = rest.decode_at_height(0).await.unwrap();
+        assert_eq!(block.block_hash().to_string(), f.genesis_block_hash);
+        assert!(rest.decode_at_height::<BlockHeader>(0).await.is_err());
+    }
     async fn blockhashbyheight_hex(f: &Fixture) {
         let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
-        let rest = new(&test_endpoint);
+        let rest = try_new(&test_endpoint).unwrap();
         let blockhash_hex = rest.call_hex("blockhashbyheight/0").await.unwrap();
         let blockhash = BlockHash::from_str(&blockhash_hex).unwrap();
         assert_eq!(blockhash.to_string(), f.genesis_block_hash);
     }
+    async fn blockhashbyheight_hex_bytes(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let hex_bytes = rest.call_hex_bytes("blockhashbyheight/0").await.unwrap();
+        let bin = rest.call_bin("blockhashbyheight/0").await.unwrap();
+        assert_eq!(hex_bytes, bin);
+        let block_hex_bytes = rest.call_hex_bytes(&["block", f.genesis_block_hash].join("/")).await.unwrap();
+        let block = Block::consensus_decode(block_hex_bytes.as_ref()).unwrap();
+        assert_eq!(block.block_hash().to_string(), f.genesis_block_hash);
+    }
+    async fn wait_for_confirmation(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let txid = Txid::from_str(f.txid_coinbase_block1).unwrap();
+        let blockhash = rest.wait_for_confirmation(&txid, 6, Duration::from_millis(10), Duration::from_secs(10)).await.unwrap();
+        assert_eq!(blockhash, rest.blockhashbyheight(1).await.unwrap());
+        let unknown = Txid::from_inner([0u8; 32]);
+        let result = rest.wait_for_confirmation(&unknown, 1, Duration::from_millis(10), Duration::from_millis(100)).await;
+        assert!(matches!(result, Err(Error::ConfirmationTimeout { in_mempool: false, .. })));
+        let result = rest.with_deadline(std::time::Instant::now() + Duration::from_millis(100))
+            .wait_for_confirmation(&unknown, 1, Duration::from_millis(10), Duration::from_secs(60)).await;
+        assert!(matches!(result, Err(Error::DeadlineExceeded)));
+    }
+    #[test]
+    fn tx_blockhash() {
+        let mempool: TxBlockHash = Context::parse_json("tx/00", r#"{"txid": "00", "vin": [], "vout": []}"#).unwrap();
+        assert_eq!(mempool.blockhash, None);
+        let confirmed: TxBlockHash = Context::parse_json("tx/00", r#"{"txid": "00", "blockhash": "ff"}"#).unwrap();
+        assert_eq!(confirmed.blockhash.as_deref(), Some("ff"));
+        let headers: Vec<HeaderConfirmations> = Context::parse_json("headers/1/ff", r#"[{"height": 1, "confirmations": -1}]"#).unwrap();
+        assert_eq!(headers[0].confirmations, -1);
+    }
     async fn utxos(f: &Fixture) {
         let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
-        let rest = new(&test_endpoint);
+        let rest = try_new(&test_endpoint).unwrap();
         let utxos = rest.getutxos(true, &vec![
             Txid::from_str(f.txid_coinbase_block1).unwrap(),
         ]).await.unwrap();
         assert!(utxos.chain_height > 0);
+        let txids = vec![Txid::from_str(f.txid_coinbase_block1).unwrap(); MAX_GETUTXOS_OUTPOINTS + 1];
+        let result = rest.getutxos(true, &txids).await;
+        assert!(matches!(result, Err(Error::TooManyOutpoints { max: MAX_GETUTXOS_OUTPOINTS })));
+    }
+    async fn utxos_bin(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let txids = vec![
+            Txid::from_str(f.txid_coinbase_block1).unwrap(),
+        ];
+        let json = rest.getutxos(false, &txids).await.unwrap();
+        let bin = rest.getutxos_bin(false, &txids).await.unwrap();
+        assert_eq!(json.chaintip_hash, bin.chaintip_hash);
+        assert_eq!(json.bitmap, bin.bitmap);
+        assert_eq!(json.utxos.len(), bin.utxos.len());
+        for (json, bin) in json.utxos.iter().zip(bin.utxos.iter()) {
+            assert_eq!(json.height, bin.height);
+            assert_eq!(json.value, bin.value);
+            assert_eq!(json.script_pub_key.hex, bin.script_pub_key.hex);
+        }
+    }
+    #[test]
+    fn utxos_diff() {
+        let snapshot = |chaintip_hash: &str, bitmap: &str| UtxoData {
+            chain_height: 0,
+            chaintip_hash: chaintip_hash.to_string(),
+            bitmap: bitmap.to_string(),
+            utxos: Vec::new(),
+        };
+        let diff = snapshot("aa", "1100").diff(&snapshot("bb", "0110"));
+        assert_eq!(diff.spent, vec![0]);
+        assert_eq!(diff.created, vec![2]);
+        assert!(diff.tip_changed);
+        let diff = snapshot("aa", "1").diff(&snapshot("aa", "1"));
+        assert!(diff.spent.is_empty() && diff.created.is_empty() && !diff.tip_changed);
+    }
+    #[test]
+    fn utxos_bin_decode() {
+        let genesis = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Bitcoin);
+        let txout = &genesis.txdata[0].output[0];
+        let mut bytes = Vec::new();
+        bytes.extend(bitcoin::consensus::serialize(&100i32));
+        bytes.extend(bitcoin::consensus::serialize(&genesis.block_hash()));
+        bytes.extend(bitcoin::consensus::serialize(&vec![0b10u8]));
+        bytes.extend(bitcoin::consensus::serialize(&VarInt(1)));
+        bytes.extend(bitcoin::consensus::serialize(&0u32));
+        bytes.extend(bitcoin::consensus::serialize(&0u32));
+        bytes.extend(bitcoin::consensus::serialize(txout));
+        let utxos = UtxoData::consensus_decode(&bytes, 3).unwrap();
+        assert_eq!(utxos.chain_height, 100);
+        assert_eq!(utxos.chaintip_hash, genesis.block_hash().to_string());
+        assert_eq!(utxos.bitmap, "010");
+        assert_eq!(utxos.utxos[0].value, Amount::from_btc(50.0).unwrap());
+        assert_eq!(utxos.utxos[0].script_pub_key.hex, txout.script_pubkey.as_bytes().to_hex());
+        let json: Utxo = serde_json::from_str(r#"{
+            "height": 0, "value": 50.00000000,
+            "scriptPubKey": {"asm": "", "hex": "", "type": "pubkey"}
+        }"#).unwrap();
+        assert_eq!(json.value, utxos.utxos[0].value);
+    }
+    #[test]
+    fn btc_amount_exact() {
+        #[derive(Deserialize, Serialize)]
+        struct Value {
+            #[serde(with="btc_amount")]
+            value: Amount,
+        }
+        let parse = |json: &str| serde_json::from_str::<Value>(json).unwrap().value;
+        assert_eq!(parse(r#"{"value": 0.00001000}"#), Amount::from_sat(1000));
+        assert_eq!(parse(r#"{"value": 20999999.97690000}"#), Amount::from_sat(2099999997690000));
+        assert_eq!(parse(r#"{"value": 1e-5}"#), Amount::from_sat(1000));
+        assert!(serde_json::from_str::<Value>(r#"{"value": -1}"#).is_err());
+        let json = serde_json::to_string(&Value { value: Amount::from_sat(2099999997690000) }).unwrap();
+        assert_eq!(parse(&json), Amount::from_sat(2099999997690000));
+    }
+    #[test]
+    fn mempool_fees_negative() {
+        let fees: MempoolFees = serde_json::from_str(
+            r#"{"base": 0.00001410, "modified": -0.09998590, "ancestor": -0.09998590, "descendant": -0.09998590}"#
+        ).unwrap();
+        assert_eq!(fees.base, Amount::from_sat(1410));
+        assert_eq!(fees.modified, SignedAmount::from_sat(-9998590));
+        let json = serde_json::to_string(&fees).unwrap();
+        let fees: MempoolFees = serde_json::from_str(&json).unwrap();
+        assert_eq!(fees.descendant, SignedAmount::from_sat(-9998590));
+    }
+    async fn tip(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let (height, blockhash) = rest.tip().await.unwrap();
+        assert_eq!(rest.tip_hash_by_height(height).await.unwrap(), blockhash);
+        let header = rest.best_block_header().await.unwrap();
+        assert!(header.block_hash() == blockhash || header.prev_blockhash == blockhash);
+    }
+    async fn params(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let params = rest.params().await.unwrap();
+        assert_eq!(params.network, Network::Bitcoin);
+        assert_eq!(params.pow_target_timespan, POW_TARGET_TIMESPAN as u64);
+    }
+    async fn network(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        assert_eq!(rest.network().await.unwrap(), Network::Bitcoin);
+        // Served from the cache.
+        assert_eq!(rest.clone().network().await.unwrap(), Network::Bitcoin);
+    }
+    async fn segwit_active(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        assert!(rest.segwit_active().await.unwrap());
+        assert!(rest.taproot_active().await.unwrap());
+        assert!(matches!(rest.deployment_active("foo").await, Err(Error::DeploymentNotFound(_))));
+    }
+    async fn genesis_hash(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        assert_eq!(rest.genesis_hash().await.unwrap().to_string(), f.genesis_block_hash);
+        assert_eq!(rest.genesis_hash().await.unwrap().to_string(), f.genesis_block_hash);
+    }
+    async fn mempool_contents(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let contents = rest.mempool_contents().await.unwrap();
+        assert!(!contents.contains_key(f.txid_coinbase_block1));
+    }
+    async fn node_status(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let status = NodeStatus::fetch(&rest).await.unwrap();
+        assert!(status.height > 0);
+        assert!(status.mempool_minfee > Amount::ZERO);
+    }
+    async fn is_in_mempool(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        assert!(!rest.is_in_mempool(&Txid::from_str(f.txid_coinbase_block1).unwrap()).await.unwrap());
+    }
+    async fn difficulty_adjustment(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let adjustment = rest.difficulty_adjustment().await.unwrap();
+        assert!(adjustment.remaining_blocks > 0);
+        assert!(adjustment.remaining_blocks <= DIFFICULTY_ADJUSTMENT_INTERVAL);
+    }
+    async fn target_at_height(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        // The first retarget happened at height 32256.
+        assert_eq!(rest.target_at_height(32255).await.unwrap(), BlockHeader::u256_from_compact_target(0x1d00ffff));
+        assert_eq!(rest.target_at_height(32256).await.unwrap(), BlockHeader::u256_from_compact_target(0x1d00d86a));
+    }
+    async fn retarget_boundaries(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let (start, end) = rest.retarget_boundaries(33000).await.unwrap();
+        assert_eq!(start.block_hash(), rest.blockhashbyheight(32256).await.unwrap());
+        assert_eq!(end.block_hash(), rest.blockhashbyheight(34271).await.unwrap());
+        let mut params = Params::new(Network::Bitcoin);
+        params.pow_target_timespan /= 2;
+        let rest = builder(&test_endpoint).params(params).build().unwrap();
+        let (start, end) = rest.retarget_boundaries(34000).await.unwrap();
+        assert_eq!(start.block_hash(), rest.blockhashbyheight(33264).await.unwrap());
+        assert_eq!(end.block_hash(), rest.blockhashbyheight(34271).await.unwrap());
+        assert!(matches!(rest.retarget_boundaries(u32::MAX).await, Err(Error::EpochOutOfRange { height: u32::MAX })));
+        let mut params = Params::new(Network::Bitcoin);
+        params.pow_target_spacing = params.pow_target_timespan + 1;
+        let rest = builder(&test_endpoint).params(params).build().unwrap();
+        assert!(matches!(rest.retarget_boundaries(34000).await, Err(Error::InvalidParams)));
+    }
+    async fn block_at_or_after_time(f: &Fixture) {
+        let test_endpoint = std::env::var(f.rest_env_name).unwrap_or(DEFAULT_ENDPOINT.to_string());
+        let rest = try_new(&test_endpoint).unwrap();
+        let (height, blockhash) = rest.block_at_or_after_time(0).await.unwrap().unwrap();
+        assert_eq!(height, 0);
+        assert_eq!(blockhash.to_string(), f.genesis_block_hash);
+        let genesis = rest.block_notxdetails(&blockhash).await.unwrap();
+        let (height, _) = rest.block_at_or_after_time(genesis.time + 1).await.unwrap().unwrap();
+        assert!(height > 0);
+        assert!(rest.block_at_or_after_time(u32::MAX).await.unwrap().is_none());
+    }
+    fn header_with_time(time: u32) -> BlockHeader {
+        let mut header = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Bitcoin).header;
+        header.time = time;
+        header
+    }
+    #[test]
+    fn difficulty_adjustment_estimate() {
+        // Blocks are found twice as fast as expected: difficulty doubles.
+        let start = header_with_time(1_000_000);
+        let tip = header_with_time(1_000_000 + 1008 * POW_TARGET_SPACING / 2);
+        let params = Params::new(Network::Bitcoin);
+        let adjustment = DifficultyAdjustment::new(&params, 2016, &start, 2016 + 1008, &tip).unwrap();
+        assert_eq!(adjustment.remaining_blocks, 1008);
+        assert_eq!(adjustment.average_block_time, 300.0);
+        assert!((adjustment.difficulty_change_percent - 100.0).abs() < 1e-9);
+        assert_eq!(adjustment.estimated_retarget_time, tip.time + 1008 * 300);
+        // No blocks mined in the window yet.
+        let adjustment = DifficultyAdjustment::new(&params, 2016, &start, 2016, &start).unwrap();
+        assert_eq!(adjustment.remaining_blocks, 2016);
+        assert_eq!(adjustment.difficulty_change_percent, 0.0);
+        // The change is clamped to a factor of four.
+        let tip = header_with_time(1_000_000 + 10);
+        let adjustment = DifficultyAdjustment::new(&params, 0, &start, 10, &tip).unwrap();
+        assert!((adjustment.difficulty_change_percent - 300.0).abs() < 1e-9);
+        // The tip must be within the window.
+        assert_eq!(DifficultyAdjustment::new(&params, 2016, &start, 2015, &start), None);
+        assert_eq!(DifficultyAdjustment::new(&params, 0, &start, 2016, &tip), None);
+        // The interval and spacing come from the params.
+        let mut params = Params::new(Network::Bitcoin);
+        params.pow_target_timespan /= 2;
+        let adjustment = DifficultyAdjustment::new(&params, 1008, &start, 1008, &start).unwrap();
+        assert_eq!(adjustment.remaining_blocks, 1008);
+        params.pow_target_spacing = 0;
+        assert_eq!(DifficultyAdjustment::new(&params, 1008, &start, 1008, &start), None);
     }
     const BTC: Fixture = Fixture {
         rest_env_name: "BITCOIN_REST_ENDPOINT",
@@ -269,14 +3392,54 @@ mod tests {
         txid_coinbase_block1: "0e3e2357e806b6cdb1f70b54c3a3a17b6714ee1f0e68bebb44a74b1efd512098",
     };
     #[tokio::test] async fn btc_decode_fail          () { decode_fail          (&BTC).await; }
+    #[tokio::test] async fn btc_connect              () { connect              (&BTC).await; }
     #[tokio::test] async fn btc_tx                   () { tx                   (&BTC).await; }
+    #[tokio::test] async fn btc_txout                () { txout                (&BTC).await; }
+    #[tokio::test] async fn btc_tx_no_witness        () { tx_no_witness        (&BTC).await; }
     #[tokio::test] async fn btc_block                () { block                (&BTC).await; }
+    #[tokio::test] async fn btc_blocks               () { blocks               (&BTC).await; }
+    #[tokio::test] async fn btc_export_blocks        () { export_blocks        (&BTC).await; }
+    #[tokio::test] async fn btc_blocks_in_order      () { blocks_in_order      (&BTC).await; }
+    #[tokio::test] async fn btc_blocks_at_heights    () { blocks_at_heights    (&BTC).await; }
+    #[tokio::test] async fn btc_miner_revenue        () { miner_revenue        (&BTC).await; }
+    #[tokio::test] async fn btc_block_fees           () { block_fees           (&BTC).await; }
+    #[tokio::test] async fn btc_block_hex_to         () { block_hex_to         (&BTC).await; }
+    #[tokio::test] async fn btc_block_output_value   () { block_output_value   (&BTC).await; }
+    #[tokio::test] async fn btc_compact_block        () { compact_block        (&BTC).await; }
+    #[tokio::test] async fn btc_coinbase_message     () { coinbase_message     (&BTC).await; }
+    #[tokio::test] async fn btc_genesis_coinbase_message() { genesis_coinbase_message(&BTC).await; }
+    #[tokio::test] async fn btc_block_integrity      () { block_integrity      (&BTC).await; }
+    #[tokio::test] async fn btc_coinbase_height      () { coinbase_height      (&BTC).await; }
+    #[tokio::test] async fn btc_witness_commitment   () { witness_commitment   (&BTC).await; }
     #[tokio::test] async fn btc_block_notxdetails    () { block_notxdetails    (&BTC).await; }
+    #[tokio::test] async fn btc_by_bytes             () { by_bytes             (&BTC).await; }
     #[tokio::test] async fn btc_headers              () { headers              (&BTC).await; }
+    #[tokio::test] async fn btc_headers_directional  () { headers_directional  (&BTC).await; }
+    #[tokio::test] async fn btc_headers_check_pow    () { headers_check_pow    (&BTC).await; }
+    #[tokio::test] async fn btc_headers_verified     () { headers_verified     (&BTC).await; }
+    #[tokio::test] async fn btc_all_headers          () { all_headers          (&BTC).await; }
+    #[tokio::test] async fn btc_headers_iter         () { headers_iter         (&BTC).await; }
     #[tokio::test] async fn btc_chaininfo            () { chaininfo            (&BTC).await; }
+    #[tokio::test] async fn btc_chaininfo_raw        () { chaininfo_raw        (&BTC).await; }
     #[tokio::test] async fn btc_blockhashbyheight    () { blockhashbyheight    (&BTC).await; }
+    #[tokio::test] async fn btc_decode_at_height     () { decode_at_height     (&BTC).await; }
     #[tokio::test] async fn btc_blockhashbyheight_hex() { blockhashbyheight_hex(&BTC).await; }
+    #[tokio::test] async fn btc_blockhashbyheight_hex_bytes() { blockhashbyheight_hex_bytes(&BTC).await; }
     #[tokio::test] async fn btc_utxos                () { utxos                (&BTC).await; }
+    #[tokio::test] async fn btc_wait_for_confirmation() { wait_for_confirmation(&BTC).await; }
+    #[tokio::test] async fn btc_utxos_bin            () { utxos_bin            (&BTC).await; }
+    #[tokio::test] async fn btc_tip                  () { tip                  (&BTC).await; }
+    #[tokio::test] async fn btc_params               () { params               (&BTC).await; }
+    #[tokio::test] async fn btc_network              () { network              (&BTC).await; }
+    #[tokio::test] async fn btc_segwit_active        () { segwit_active        (&BTC).await; }
+    #[tokio::test] async fn btc_genesis_hash         () { genesis_hash         (&BTC).await; }
+    #[tokio::test] async fn btc_mempool_contents     () { mempool_contents     (&BTC).await; }
+    #[tokio::test] async fn btc_node_status          () { node_status          (&BTC).await; }
+    #[tokio::test] async fn btc_is_in_mempool        () { is_in_mempool        (&BTC).await; }
+    #[tokio::test] async fn btc_difficulty_adjustment() { difficulty_adjustment(&BTC).await; }
+    #[tokio::test] async fn btc_target_at_height     () { target_at_height     (&BTC).await; }
+    #[tokio::test] async fn btc_retarget_boundaries  () { retarget_boundaries  (&BTC).await; }
+    #[tokio::test] async fn btc_block_at_or_after_time() { block_at_or_after_time(&BTC).await; }
     const MONA: Fixture = Fixture {
         rest_env_name: "MONACOIN_REST_ENDPOINT",
         genesis_block_hash: "ff9f1c0116d19de7c9963845e129f9ed1bfc0b376eb54fd7afa42e0d418c8bb6",
@@ -286,9 +3449,14 @@ mod tests {
     #[tokio::test] async fn mona_tx               () { tx               (&MONA).await; }
     #[tokio::test] async fn mona_block            () { block            (&MONA).await; }
     #[tokio::test] async fn mona_block_notxdetails() { block_notxdetails(&MONA).await; }
+    #[tokio::test] async fn mona_by_bytes         () { by_bytes         (&MONA).await; }
+    #[tokio::test] async fn mona_coinbase_message () { coinbase_message (&MONA).await; }
     #[tokio::test] async fn mona_headers          () { headers          (&MONA).await; }
-    #[cfg(not(feature="softforks"))]
+    #[tokio::test] async fn mona_headers_directional() { headers_directional(&MONA).await; }
+    #[tokio::test] async fn mona_headers_verified () { headers_verified (&MONA).await; }
     #[tokio::test] async fn mona_chaininfo        () { chaininfo        (&MONA).await; }
     //#[tokio::test] async fn mona_blockhashbyheight() { blockhashbyheight(&MONA).await; }
     #[tokio::test] async fn mona_utxos            () { utxos            (&MONA).await; }
+    #[tokio::test] async fn mona_txout            () { txout            (&MONA).await; }
+    #[tokio::test] async fn mona_is_in_mempool    () { is_in_mempool    (&MONA).await; }
 }